@@ -0,0 +1,257 @@
+//! The checker runs after parsing and enforces the rules of Core the grammar cannot express:
+//! every identifier is declared exactly once in the <DECL SEQ>, and every declared variable is
+//! used by some statement. It also warns about variables that may be read before they are
+//! given a value.
+
+use std::collections::{HashMap, HashSet};
+use error::{CoreError, Diagnostic};
+use parser::{Program, Id, Stmt, StmtKind, Cond, Exp, Trm, Op};
+
+/// Checks `program`, returning its semantic errors followed by its warnings.
+pub fn check_prog(program: &Program) -> Vec<CoreError> {
+    let mut errors: Vec<CoreError> = Vec::new();
+    let mut warnings: Vec<CoreError> = Vec::new();
+
+    // Every declaration, mapped to whether a statement uses it.
+    let mut declared: HashMap<&str, (&Id, bool)> = HashMap::new();
+    for decl in &program.decl_seq {
+        for id in &decl.id_list {
+            if let Some(&(first, _)) = declared.get(id.name.as_str()) {
+                let message: String = format!("{} is declared more than once", id.name);
+                let hint: String = format!("{} was first declared at {}; remove this declaration",
+                                           id.name, first.span.position());
+                errors.push(CoreError::Semantic(Diagnostic::new(&message, id.span)
+                                                    .with_hint(&hint)));
+            } else {
+                declared.insert(&id.name, (id, false));
+            }
+        }
+    }
+
+    let mut uses: Vec<&Id> = Vec::new();
+    collect_stmt_seq(&program.stmt_seq, &mut uses);
+
+    let mut reported: Vec<&str> = Vec::new();
+    for id in uses {
+        match declared.get_mut(id.name.as_str()) {
+            Some(&mut (_, ref mut used)) => *used = true,
+            None if !reported.contains(&id.name.as_str()) => {
+                reported.push(&id.name);
+                let message: String = format!("{} is used but never declared", id.name);
+                let hint: String = format!("declare it before 'begin', e.g. int {};", id.name);
+                errors.push(CoreError::Semantic(Diagnostic::new(&message, id.span)
+                                                    .with_hint(&hint)));
+            },
+            None => {},
+        }
+    }
+
+    // Reporting unused variables in the order they were declared.
+    let mut unused: Vec<&Id> = declared.values()
+        .filter(|&&(_, used)| !used)
+        .map(|&(id, _)| id)
+        .collect();
+    unused.sort_by_key(|id| id.span.offset);
+    for id in unused {
+        let message: String = format!("{} is declared but never used", id.name);
+        warnings.push(CoreError::Semantic(Diagnostic::new(&message, id.span)
+                                              .with_hint("remove the declaration")
+                                              .as_warning()));
+    }
+
+    let mut initialization: Initialization = Initialization {
+        declared: declared.keys().cloned().collect(),
+        reported: HashSet::new(),
+        warnings: Vec::new(),
+    };
+    initialization.check_stmt_seq(&program.stmt_seq, &mut HashSet::new());
+    warnings.extend(initialization.warnings);
+
+    errors.extend(warnings);
+    errors
+}
+
+/// Tracks which variables are definitely assigned at each point of the program, warning about
+/// reads of any that are not.
+struct Initialization<'a> {
+    declared: HashSet<&'a str>,
+
+    /// Variables already warned about, so each is reported once.
+    reported: HashSet<&'a str>,
+    warnings: Vec<CoreError>,
+}
+
+impl<'a> Initialization<'a> {
+    /// Checks a statement sequence, adding each variable it definitely assigns to `assigned`.
+    fn check_stmt_seq(&mut self, stmt_seq: &'a [Stmt], assigned: &mut HashSet<&'a str>) {
+        for stmt in stmt_seq {
+            match stmt.kind {
+                StmtKind::Assign(ref id, ref exp) => {
+                    self.check_uses(|uses| collect_exp(exp, uses), assigned);
+                    assigned.insert(&id.name);
+                },
+                StmtKind::If(ref cond, ref then_seq, ref else_seq) => {
+                    self.check_uses(|uses| collect_cond(cond, uses), assigned);
+
+                    // Only what both branches assign is assigned afterwards.
+                    let mut then_assigned: HashSet<&str> = assigned.clone();
+                    self.check_stmt_seq(then_seq, &mut then_assigned);
+                    let mut else_assigned: HashSet<&str> = assigned.clone();
+                    if let Some(ref else_seq) = *else_seq {
+                        self.check_stmt_seq(else_seq, &mut else_assigned);
+                    }
+                    assigned.extend(then_assigned.intersection(&else_assigned));
+                },
+                StmtKind::Loop(ref cond, ref stmt_seq) => {
+                    self.check_uses(|uses| collect_cond(cond, uses), assigned);
+
+                    // The body might not run at all, so nothing it assigns carries past the loop.
+                    self.check_stmt_seq(stmt_seq, &mut assigned.clone());
+                },
+                StmtKind::In(ref id_list) => {
+                    assigned.extend(id_list.iter().map(|id| id.name.as_str()));
+                },
+                StmtKind::Out(ref id_list) => {
+                    self.check_uses(|uses| uses.extend(id_list), assigned);
+                },
+            }
+        }
+    }
+
+    fn check_uses<F: FnOnce(&mut Vec<&'a Id>)>(&mut self, collect: F, assigned: &HashSet<&'a str>) {
+        let mut uses: Vec<&Id> = Vec::new();
+        collect(&mut uses);
+
+        for id in uses {
+            let name: &str = &id.name;
+            if assigned.contains(name) || !self.declared.contains(name)
+                || !self.reported.insert(name) {
+                continue;
+            }
+
+            let message: String = format!("{} may be used before it is assigned a value", name);
+            let hint: String = format!("assign {} or read it first; until then it is 0", name);
+            self.warnings.push(CoreError::Semantic(Diagnostic::new(&message, id.span)
+                                                       .with_hint(&hint)
+                                                       .as_warning()));
+        }
+    }
+}
+
+/// Gathers every identifier a statement sequence refers to, in source order.
+fn collect_stmt_seq<'a>(stmt_seq: &'a [Stmt], uses: &mut Vec<&'a Id>) {
+    for stmt in stmt_seq {
+        match stmt.kind {
+            StmtKind::Assign(ref id, ref exp) => {
+                uses.push(id);
+                collect_exp(exp, uses);
+            },
+            StmtKind::If(ref cond, ref then_seq, ref else_seq) => {
+                collect_cond(cond, uses);
+                collect_stmt_seq(then_seq, uses);
+                if let Some(ref else_seq) = *else_seq {
+                    collect_stmt_seq(else_seq, uses);
+                }
+            },
+            StmtKind::Loop(ref cond, ref stmt_seq) => {
+                collect_cond(cond, uses);
+                collect_stmt_seq(stmt_seq, uses);
+            },
+            StmtKind::In(ref id_list) | StmtKind::Out(ref id_list) => uses.extend(id_list),
+        }
+    }
+}
+
+fn collect_cond<'a>(cond: &'a Cond, uses: &mut Vec<&'a Id>) {
+    match *cond {
+        Cond::Comp(ref comp) => {
+            collect_op(&comp.lhs, uses);
+            collect_op(&comp.rhs, uses);
+        },
+        Cond::Not(ref cond) => collect_cond(cond, uses),
+        Cond::And(ref lhs, ref rhs) | Cond::Or(ref lhs, ref rhs) => {
+            collect_cond(lhs, uses);
+            collect_cond(rhs, uses);
+        },
+    }
+}
+
+fn collect_exp<'a>(exp: &'a Exp, uses: &mut Vec<&'a Id>) {
+    match *exp {
+        Exp::Trm(ref trm) => collect_trm(trm, uses),
+        Exp::Add(ref exp, _, ref trm) | Exp::Sub(ref exp, _, ref trm) => {
+            collect_exp(exp, uses);
+            collect_trm(trm, uses);
+        },
+    }
+}
+
+fn collect_trm<'a>(trm: &'a Trm, uses: &mut Vec<&'a Id>) {
+    match *trm {
+        Trm::Op(ref op) => collect_op(op, uses),
+        Trm::Mul(ref trm, _, ref op) => {
+            collect_trm(trm, uses);
+            collect_op(op, uses);
+        },
+    }
+}
+
+fn collect_op<'a>(op: &'a Op, uses: &mut Vec<&'a Id>) {
+    match *op {
+        Op::Int(..) => {},
+        Op::Id(ref id) => uses.push(id),
+        Op::Exp(ref exp) => collect_exp(exp, uses),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use error::{CoreError, Position};
+    use tokenizer;
+    use parser;
+
+    fn check(source: &str) -> Vec<CoreError> {
+        let lexemes = tokenizer::tokenize(source.as_bytes()).unwrap();
+        super::check_prog(&parser::init_parser(lexemes).unwrap())
+    }
+
+    #[test]
+    fn reports_undeclared_duplicate_and_unused_names() {
+        let errors: Vec<CoreError> = check("program int X, Y, X; int Z;\n\
+                                            begin read X; Y = W + W; write Y; end");
+        let summary: Vec<(String, bool, Option<Position>)> = errors.iter()
+            .map(|error| (error.message().to_string(), error.is_warning(), error.position()))
+            .collect();
+
+        assert_eq!(summary, vec![
+            ("X is declared more than once".to_string(), false,
+             Some(Position { line: 1, column: 19 })),
+            ("W is used but never declared".to_string(), false,
+             Some(Position { line: 2, column: 19 })),
+            ("Z is declared but never used".to_string(), true,
+             Some(Position { line: 1, column: 26 })),
+        ]);
+    }
+
+    #[test]
+    fn warns_about_reads_that_may_come_before_assignment() {
+        let errors: Vec<CoreError> = check("program int A, B, C, D;\n\
+                                            begin\n\
+                                            if (A > 0) then B = 1; C = 1; else B = 2; end;\n\
+                                            while (B > 0) loop D = B; B = B - D; end;\n\
+                                            write B, C, D, C;\n\
+                                            end");
+        let summary: Vec<(String, Option<Position>)> = errors.iter()
+            .map(|error| (error.message().to_string(), error.position()))
+            .collect();
+
+        assert_eq!(summary, vec![
+            ("A may be used before it is assigned a value".to_string(),
+             Some(Position { line: 3, column: 5 })),
+            ("C may be used before it is assigned a value".to_string(),
+             Some(Position { line: 5, column: 10 })),
+            ("D may be used before it is assigned a value".to_string(),
+             Some(Position { line: 5, column: 13 })),
+        ]);
+    }
+}
@@ -0,0 +1,233 @@
+//! The commands the `core` binary runs: each reads a program, reports its errors on stderr
+//! against the source, and prints what the command asks for.
+
+use std::fs;
+use std::io;
+use core_interpreter::{check, diagnostic, executor, format, parse, parser, tokenizer};
+use core_interpreter::{Checked, ConsoleIo, CoreError, Io, IteratorIo, Options};
+use core_interpreter::tokenizer::TokenFormat;
+use core_interpreter::value::Value;
+
+/// Where the source of a Core program comes from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Source {
+    /// A file, named by its path.
+    File(String),
+
+    /// Everything on standard input, named `-` on the command line.
+    Stdin,
+
+    /// Text given on the command line with `-e`.
+    Inline(String),
+}
+
+impl Source {
+    /// Picks the source a command-line argument names: `-` for standard input, or a path.
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" { Source::Stdin } else { Source::File(arg.to_string()) }
+    }
+
+    /// The name errors in the source are reported against.
+    pub fn name(&self) -> &str {
+        match *self {
+            Source::File(ref file) => file,
+            Source::Stdin => "<stdin>",
+            Source::Inline(..) => "<inline>",
+        }
+    }
+
+    pub fn is_file(&self) -> bool {
+        matches!(*self, Source::File(..))
+    }
+
+    /// Reads the source's raw bytes.
+    pub fn read(&self) -> Result<Vec<u8>, CoreError> {
+        match *self {
+            Source::File(ref file) => tokenizer::read_file(file),
+            Source::Stdin => tokenizer::read_source(io::stdin(), self.name()),
+            Source::Inline(ref text) => Ok(text.clone().into_bytes()),
+        }
+    }
+}
+
+/// How to invoke the interpreter, in brief.
+pub fn usage() -> String {
+    /*
+     * A simple summary that helps the user understand how to use our interpreter. `help` gives
+     * the full story.
+     */
+    "Usage: ./core [run] <core-source-file-name> [options]\n       \
+     ./core tokens [--names | --json] <core-source-file-name>\n       \
+     ./core parse <core-source-file-name>\n       \
+     ./core check <core-source-file-name>\n       \
+     ./core fmt [--check] [--indent <width>] <core-source-file-name>\n       \
+     ./core repl [options]\n       \
+     ./core test <test-directory>\n".to_string()
+}
+
+/// How to invoke the interpreter, in full: every subcommand, option and exit code.
+pub fn help() -> String {
+    format!("{}\n\
+             Commands:\n    \
+                 run       Run a program (the default when no command is given)\n    \
+                 tokens    Print a program's tokens as course codes, names or JSON\n    \
+                 parse     Print a program in canonical form without running it\n    \
+                 check     Report a program's errors and warnings without running it\n    \
+                 fmt       Rewrite a program in canonical form\n    \
+                 repl      Run declarations and statements as they are typed\n    \
+                 test      Run the golden-file tests under a directory\n\
+             \n\
+             A <core-source-file-name> of `-` reads the program from stdin, and `-e <source>`\n\
+             may be given in its place to take the program from the command line. A program run\n\
+             from stdin takes the values of `read` from `--input`, as stdin holds the program.\n\
+             \n\
+             Options:\n    \
+                 --input <input-file-name>   Take the values of `read` from a file\n    \
+                 --trace                     Report each statement as it runs\n    \
+                 --right-assoc               Group `-` and `*` chains from the right\n    \
+                 --wrapping | --saturating | --bigint\n                                \
+                 How to handle values that overflow 32 bits\n    \
+                 --strict-init               Make reading an unassigned variable an error\n    \
+                 -h, --help                  Print this help\n    \
+                 -V, --version               Print the interpreter's version\n\
+             \n\
+             Exit codes:\n    \
+                 0  success\n    \
+                 1  unformatted source (fmt --check) or failing tests\n    \
+                 2  usage error\n    \
+                 3  lexical error\n    \
+                 4  syntax error\n    \
+                 5  semantic error\n    \
+                 6  runtime error\n    \
+                 7  I/O error\n",
+            usage())
+}
+
+/// Renders `errors` on stderr against `source`, the text of the source named `file`.
+fn report(errors: &[CoreError], file: &str, source: &[u8]) {
+    for error in errors {
+        eprint!("{}", diagnostic::render(error, file, source));
+    }
+}
+
+/// Passes `result` through, reporting its errors first if it failed.
+pub fn reported<T>(result: Result<T, Vec<CoreError>>, file: &str, source: &[u8])
+               -> Result<T, Vec<CoreError>> {
+    result.inspect_err(|errors| report(errors, file, source))
+}
+
+/// Reads the text of `origin`, reporting the error if it cannot be read.
+fn read(origin: &Source) -> Result<Vec<u8>, Vec<CoreError>> {
+    reported(origin.read().map_err(|error| vec![error]), origin.name(), &[])
+}
+
+/// Reads the values for `read` statements from `input_file`, if one is given.
+pub fn read_input(input_file: Option<&str>) -> Result<Option<Vec<Value>>, Vec<CoreError>> {
+    match input_file {
+        Some(input_file) => {
+            let input = executor::read_input_file(input_file).map_err(|error| vec![error]);
+            reported(input, input_file, &[]).map(Some)
+        },
+        None => Ok(None),
+    }
+}
+
+/// Runs a Core program. When `input_file` is given, `read` statements take their values from it
+/// rather than prompting on stdin.
+pub fn init_driver(origin: &Source, input_file: Option<&str>, options: Options)
+                   -> Result<(), Vec<CoreError>> {
+    let file: &str = origin.name();
+    let source: Vec<u8> = read(origin)?;
+    let input: Option<Vec<Value>> = match read_input(input_file)? {
+        // Stdin has already been read to its end, so a `read` finds no values there.
+        None if *origin == Source::Stdin => Some(Vec::new()),
+        input => input,
+    };
+
+    reported(run_source(file, &source, input, options), file, &source)
+}
+
+fn run_source(file: &str, source: &[u8], input: Option<Vec<Value>>, options: Options)
+              -> Result<(), Vec<CoreError>> {
+    let checked: Checked = check(source)?;
+    report(&checked.warnings, file, source);
+
+    // Without `--input`, `read` statements prompt on the terminal.
+    let mut console: ConsoleIo = ConsoleIo;
+    let mut supplied: IteratorIo<_>;
+    let io: &mut dyn Io = match input {
+        Some(input) => {
+            supplied = IteratorIo::new(input);
+            &mut supplied
+        },
+        None => &mut console,
+    };
+
+    core_interpreter::run_with_options(&checked.program, io, options).map_err(|error| vec![error])
+}
+
+/// Rewrites a Core source file in canonical form, indenting `indent` spaces per level. With
+/// `check`, the file is left as it is. Returns whether the file is now formatted. Source that is
+/// not from a file is printed in canonical form instead.
+pub fn init_formatter(origin: &Source, check: bool, indent: usize)
+                      -> Result<bool, Vec<CoreError>> {
+    let file: &str = origin.name();
+    let source: Vec<u8> = read(origin)?;
+    let formatted: String = reported(format(&source, indent), file, &source)?;
+
+    let unchanged: bool = formatted.as_bytes() == &source[..];
+    if check {
+        if !unchanged {
+            eprintln!("{} is not formatted", file);
+        }
+        return Ok(unchanged);
+    }
+
+    // Source from stdin or the command line has nowhere to be written back to.
+    if !origin.is_file() {
+        print!("{}", formatted);
+        return Ok(true);
+    }
+
+    if unchanged {
+        return Ok(true);
+    }
+
+    let written = fs::write(file, formatted)
+        .map_err(|e| vec![CoreError::Io(format!("{}: {}", file, e))]);
+    reported(written, file, &[]).map(|()| true)
+}
+
+/// Parses and checks a Core program without running it, reporting every error and warning.
+pub fn init_checker(origin: &Source) -> Result<(), Vec<CoreError>> {
+    let file: &str = origin.name();
+    let source: Vec<u8> = read(origin)?;
+
+    let checked: Checked = reported(check(&source), file, &source)?;
+    report(&checked.warnings, file, &source);
+    Ok(())
+}
+
+/// Parses a Core program and prints it back in canonical form, without checking or running
+/// it.
+pub fn init_parse_printer(origin: &Source) -> Result<(), Vec<CoreError>> {
+    let file: &str = origin.name();
+    let source: Vec<u8> = read(origin)?;
+
+    let program: parser::Program = reported(parse(&source), file, &source)?;
+    print!("{}", parser::print_prog(&program));
+    Ok(())
+}
+
+/// Prints the tokens of a Core program in `format`.
+pub fn init_token_printer(origin: &Source, format: TokenFormat)
+                          -> Result<(), Vec<CoreError>> {
+    let file: &str = origin.name();
+    let source: Vec<u8> = read(origin)?;
+
+    let (listing, errors): (String, Vec<CoreError>) = tokenizer::list_tokens(&source, format);
+    print!("{}", listing);
+    report(&errors, file, &source);
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
@@ -0,0 +1,106 @@
+//! Renders a `CoreError` in the style of rustc: the offending source line, a caret underline
+//! beneath the tokens at fault, and a hint for fixing it.
+
+use error::CoreError;
+
+/// Formats `error` against the contents of the Core source file it was raised for.
+///
+/// ```text
+/// error[syntax]: expected ';', found 'write'
+///  --> program.core:3:5
+///   |
+/// 3 |     write X;
+///   |     ^^^^^
+///   = hint: missing ';' after assignment
+/// ```
+pub fn render(error: &CoreError, file: &str, source: &[u8]) -> String {
+    let mut output: String = format!("{}[{}]: {}\n", error.severity(), error.kind(),
+                                     error.message());
+
+    // A default span (line 0) marks an error with no place in the source.
+    let span = match error.span() {
+        Some(span) if span.line > 0 => span,
+        _ => return output,
+    };
+
+    let line_number: String = span.line.to_string();
+    let gutter: String = " ".repeat(line_number.len());
+    let text: String = source_line(source, span.line);
+
+    output.push_str(&format!("{}--> {}:{}:{}\n", gutter, file, span.line, span.column));
+    output.push_str(&format!("{} |\n", gutter));
+    output.push_str(&format!("{} | {}\n", line_number, text));
+    output.push_str(&format!("{} | {}\n", gutter, underline(&text, span.column, span.length)));
+
+    if let Some(hint) = error.hint() {
+        output.push_str(&format!("{} = hint: {}\n", gutter, hint));
+    }
+
+    output
+}
+
+/// Fetches the 1-based `line` of `source`, without its line ending.
+fn source_line(source: &[u8], line: usize) -> String {
+    let text: String = String::from_utf8_lossy(source).into_owned();
+
+    match text.split('\n').nth(line - 1) {
+        Some(line) => line.trim_end_matches('\r').to_string(),
+        None => String::new(),
+    }
+}
+
+/// Builds the caret line for a span starting at the 1-based `column` of `text`. Tabs before the
+/// span are kept so the carets line up however the terminal renders them.
+fn underline(text: &str, column: usize, length: usize) -> String {
+    let bytes: &[u8] = text.as_bytes();
+    let start: usize = column.saturating_sub(1);
+    let mut output: String = String::new();
+
+    for index in 0..start {
+        match bytes.get(index) {
+            Some(&b'\t') => output.push('\t'),
+            _ => output.push(' '),
+        }
+    }
+
+    // Spans that run past the end of the line (block comments, EOF) are cut off at the line end.
+    let remaining: usize = if bytes.len() > start { bytes.len() - start } else { 0 };
+    let carets: usize = if length < remaining { length } else { remaining };
+    output.push_str(&"^".repeat(if carets == 0 { 1 } else { carets }));
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use error::{CoreError, Diagnostic};
+    use tokenizer::Span;
+
+    #[test]
+    fn underlines_offending_token_with_hint() {
+        let source: &[u8] = b"program int X; begin\r\n\tX = 1\r\n\twrite X;\r\nend\r\n";
+        let error = CoreError::Syntax(
+            Diagnostic::new("expected ';', found 'write'",
+                            Span { offset: 30, line: 3, column: 2, length: 5 })
+                .with_hint("missing ';' after assignment"));
+
+        let expected: &str = "error[syntax]: expected ';', found 'write'\n \
+                              --> test.core:3:2\n  \
+                              |\n\
+                              3 | \twrite X;\n  \
+                              | \t^^^^^\n  \
+                              = hint: missing ';' after assignment\n";
+
+        assert_eq!(super::render(&error, "test.core", source), expected);
+    }
+
+    #[test]
+    fn leaves_out_the_excerpt_for_errors_with_no_position() {
+        let error = CoreError::Syntax(Diagnostic::new("expected 'program', found end of file",
+                                                      Span::default()));
+
+        assert_eq!(super::render(&error, "test.core", b""),
+                   "error[syntax]: expected 'program', found end of file\n");
+        assert_eq!(super::underline("X = 1;", 0, 1), "^");
+    }
+}
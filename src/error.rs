@@ -0,0 +1,162 @@
+//! Errors raised while tokenizing, parsing, checking or executing a Core program.
+
+use std::error::Error;
+use std::fmt;
+use tokenizer::Span;
+
+/// A 1-based line and column in the Core source file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Whether a diagnostic stops the program from running.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Severity {
+    #[default]
+    Error,
+
+    /// Worth fixing, but the program can still run.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// What went wrong in a Core source file, and where.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    /// A short suggestion for fixing the error, shown beneath the source excerpt.
+    pub hint: Option<String>,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    pub fn new(message: &str, span: Span) -> Diagnostic {
+        Diagnostic { message: message.to_string(), span, hint: None, severity: Severity::Error }
+    }
+
+    pub fn with_hint(mut self, hint: &str) -> Diagnostic {
+        self.hint = Some(hint.to_string());
+        self
+    }
+
+    pub fn as_warning(mut self) -> Diagnostic {
+        self.severity = Severity::Warning;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CoreError {
+    /// The source file could not be read.
+    Io(String),
+
+    /// An illegal token was encountered while tokenizing.
+    Lexical(Diagnostic),
+
+    /// The token stream does not match the Core grammar.
+    Syntax(Diagnostic),
+
+    /// The program is grammatically valid but not a legal Core program.
+    Semantic(Diagnostic),
+
+    /// The program failed while it was being executed.
+    Runtime(Diagnostic),
+}
+
+impl CoreError {
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match *self {
+            CoreError::Io(_) => None,
+            CoreError::Lexical(ref diagnostic) |
+            CoreError::Syntax(ref diagnostic) |
+            CoreError::Semantic(ref diagnostic) |
+            CoreError::Runtime(ref diagnostic) => Some(diagnostic),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match *self {
+            CoreError::Io(ref message) => message,
+            _ => &self.diagnostic().unwrap().message,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.diagnostic().map(|diagnostic| diagnostic.span)
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        self.span().map(|span| span.position())
+    }
+
+    pub fn hint(&self) -> Option<&str> {
+        match self.diagnostic() {
+            Some(&Diagnostic { hint: Some(ref hint), .. }) => Some(hint),
+            _ => None,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self.diagnostic() {
+            Some(diagnostic) => diagnostic.severity,
+            None => Severity::Error,
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity() == Severity::Warning
+    }
+
+    /// A short name for the stage of the interpreter that raised the error.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            CoreError::Io(_) => "io",
+            CoreError::Lexical(..) => "lexical",
+            CoreError::Syntax(..) => "syntax",
+            CoreError::Semantic(..) => "semantic",
+            CoreError::Runtime(..) => "runtime",
+        }
+    }
+
+    /// The exit status of a `core` invocation that failed with this error. 1 and 2 are taken by
+    /// failed checks and usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            CoreError::Lexical(..) => 3,
+            CoreError::Syntax(..) => 4,
+            CoreError::Semantic(..) => 5,
+            CoreError::Runtime(..) => 6,
+            CoreError::Io(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position() {
+            Some(position) => {
+                write!(f, "{} {} at {}: {}", self.kind(), self.severity(), position, self.message())
+            },
+            None => write!(f, "{} {}: {}", self.kind(), self.severity(), self.message()),
+        }
+    }
+}
+
+impl Error for CoreError {}
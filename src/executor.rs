@@ -1,554 +1,836 @@
 #![allow(dead_code)]
-#![allow(unused_mut)]
-#![allow(unused_imports)]
-#![allow(unused_variables)]
-#![allow(unused_assignments)]
-#![allow(unused_must_use)]
+
+//! The executor walks the parser's abstract syntax tree and runs the Core program.
 
 use std::io;
-use std::io::stdout;
+use std::io::{stderr, stdout};
 use std::io::Write;
-use std::ops::Index;
-use tokenizer::Token;
-use parser::ParseTree;
-use std::collections::HashMap;
-
-pub fn init_executor(file_tokens: Vec<Token>) {
-    let mut this_execute_tree = ParseTree {
-        tokens: file_tokens.clone(),
-        memory: HashMap::new(),
-        current_statement: "".to_string(),
-        statements: Vec::new(),
-        context: Vec::new(),
-        state: 0,
-        depth: 0
-    };
+use std::slice;
+use std::collections::{HashMap, VecDeque};
+use error::{CoreError, Diagnostic};
+use tokenizer;
+use tokenizer::Span;
+use parser;
+use value::Value;
+use parser::{Program, Decl, Id, Stmt, StmtKind, Cond, Comp, CompOp, Exp, Trm, Op};
+
+/// Where `read` statements take their values from and where `write` statements send them.
+pub trait Io {
+    /// Supplies the value to store in variable `id`, or `None` once input has run out.
+    fn read_int(&mut self, id: &str) -> io::Result<Option<Value>>;
+
+    fn write_int(&mut self, value: &Value) -> io::Result<()>;
+
+    /// Reports a statement that has just run, when tracing is on.
+    fn trace(&mut self, line: &str) -> io::Result<()> {
+        writeln!(stderr(), "trace: {}", line)
+    }
+}
 
-    execute_prog(&mut this_execute_tree);
+/// Prompts the user on the terminal for each value read, writing output to stdout.
+pub struct ConsoleIo;
 
-    /*
-     * For writing tests later:
-     *
-     * println!("{}", this_execute_tree.get_token());
-     * edit_tree(&mut this_execute_tree);
-     * println!("{}", this_execute_tree.get_token());
-     * println!("{}", this_execute_tree.retrieve_variable("testing"));
-     * println!("{}", this_execute_tree.retrieve_variable("should_not_work"));
-     */
-}
+impl Io for ConsoleIo {
+    fn read_int(&mut self, id: &str) -> io::Result<Option<Value>> {
+        print!("{}: ", id);
+        stdout().flush()?;
 
-fn execute_prog(mut tree: &mut ParseTree) {
-    if tree.get_token().eq(&Token::Program) {
-        tree.descend();
-        tree.next();
-        execute_decl_seq(&mut tree);
-        tree.ascend();
-        if tree.get_token().eq(&Token::Begin) {
-            tree.descend();
-            tree.next(); // Consume the 'begin' keyword
-            execute_stmt_seq(&mut tree);
-            if tree.get_token().eq(&Token::End) {
-                // tree.ascend();
-                // for line in tree.output_stream.clone() {
-                //     println!("{}", line);
-                // }
-            } else {
-                panic!("execute_prog: expected 'end'");
+        loop {
+            let mut input_text = String::new();
+            if io::stdin().read_line(&mut input_text)? == 0 {
+                return Ok(None);
             }
-        } else {
-            panic!("execute_prog: expected 'begin'");
+
+            let trimmed = input_text.trim();
+            match trimmed.parse::<Value>() {
+                Ok(value) => return Ok(Some(value)),
+                Err(..) => println!("You entered {} but Core expected an integer value.", trimmed)
+            };
         }
-    } else {
-        panic!("program @ {} : {}", tree.state, tree.tokens[tree.state as usize]);
+    }
+
+    fn write_int(&mut self, value: &Value) -> io::Result<()> {
+        writeln!(stdout(), "{}", value)
     }
 }
 
-fn execute_decl_seq(mut tree: &mut ParseTree) {
+/// Takes each value read from an iterator without prompting, writing output to stdout.
+pub struct IteratorIo<I> {
+    input: I,
+}
 
-    // <DECL>
-    // <DECL> <DECL SEQ>
+impl<I: Iterator> IteratorIo<I> where I::Item: Into<Value> {
+    pub fn new<T: IntoIterator<Item = I::Item, IntoIter = I>>(input: T) -> IteratorIo<I> {
+        IteratorIo { input: input.into_iter() }
+    }
+}
 
-    execute_decl(&mut tree); // parses declaration and moves on
+impl<I: Iterator> Io for IteratorIo<I> where I::Item: Into<Value> {
+    fn read_int(&mut self, _id: &str) -> io::Result<Option<Value>> {
+        Ok(self.input.next().map(Into::into))
+    }
 
-    if tree.get_token().eq(&Token::Int) {
-        execute_decl_seq(&mut tree);
+    fn write_int(&mut self, value: &Value) -> io::Result<()> {
+        writeln!(stdout(), "{}", value)
     }
 }
 
-fn execute_decl(mut tree: &mut ParseTree) {
-
-    // int <ID LIST>;
+/// Reads from and writes to memory, never touching the process's stdio.
+#[derive(Debug, Default, Clone)]
+pub struct BufferIo {
+    pub input: VecDeque<Value>,
+    pub output: Vec<Value>,
+    pub trace: Vec<String>,
+}
 
-    if tree.get_token().eq(&Token::Int) {
-        tree.next(); // consume int
-        execute_id_list(&mut tree);
-        if tree.get_token().eq(&Token::Semicolon) {
-            tree.next(); // an entire declaration has been consumed; move on
-        } else {
-            panic!("execute_decl: expected ';'");
+impl BufferIo {
+    pub fn new<T: Into<Value>>(input: Vec<T>) -> BufferIo {
+        BufferIo {
+            input: input.into_iter().map(Into::into).collect(),
+            output: Vec::new(),
+            trace: Vec::new(),
         }
-    } else {
-        panic!("execute_decl: expected 'int'");
     }
 }
 
-fn execute_stmt_seq(mut tree: &mut ParseTree) {
-
-    // <STMT>
-    // <STMT> <STMT SEQ>
-
-    let mut id_flag: bool = false;
-    let mut match_flag: bool = false;
-
-    match tree.get_token() {
-        &Token::Identifier(ref i) => {
-            id_flag = true;
-            match_flag = true;
-        },
-        _ => print!("")
+impl Io for BufferIo {
+    fn read_int(&mut self, _id: &str) -> io::Result<Option<Value>> {
+        Ok(self.input.pop_front())
     }
 
-    if tree.get_token().eq(&Token::Read) {
-        match_flag = true;
-        execute_in(&mut tree);
-    } else if tree.get_token().eq(&Token::Write) {
-        match_flag = true;
-        execute_out(&mut tree);
-    } else if tree.get_token().eq(&Token::If) {
-        match_flag = true;
-        execute_if(&mut tree);
-    } else if tree.get_token().eq(&Token::While) {
-        match_flag = true;
-        execute_loop(&mut tree);
+    fn write_int(&mut self, value: &Value) -> io::Result<()> {
+        self.output.push(value.clone());
+        Ok(())
     }
 
-    if id_flag {
-        execute_assign(&mut tree);
+    fn trace(&mut self, line: &str) -> io::Result<()> {
+        self.trace.push(line.to_string());
+        Ok(())
     }
+}
 
-    if match_flag {
-        execute_stmt_seq(&mut tree);
-    }
+/// How chains of `+`/`-` and of `*` are grouped when they are evaluated.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Associativity {
+    /// `10 - 3 - 2` is `(10 - 3) - 2`, as in ordinary arithmetic.
+    #[default]
+    Left,
+
+    /// `10 - 3 - 2` is `10 - (3 - 2)`, following the grammar's recursion. Earlier releases of
+    /// the interpreter evaluated this way, so it is kept for reproducing their output.
+    Right,
 }
 
-fn execute_id_list(mut tree: &mut ParseTree) {
+/// What happens when a value does not fit in 32 bits, whether it is the result of `+`, `-` or
+/// `*`, an integer literal, or a value that was read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Overflow {
+    /// Stops the program with a runtime error.
+    #[default]
+    Checked,
 
-    // <ID>
-    // <ID>, <ID LIST>
+    /// Wraps around in two's complement, so `2147483647 + 1` is `-2147483648`.
+    Wrapping,
 
-    let mut identifier: String;
-    let mut id_result: (String, ParseTree);
-    execute_id(&mut tree);
+    /// Clamps to the nearest representable value, so `2147483647 + 1` is `2147483647`.
+    Saturating,
 
-    if tree.get_token().eq(&Token::Comma) {
-        tree.next(); // consume comma
-        execute_id_list(&mut tree);
-    }
+    /// Grows values past 32 bits as needed, so arithmetic is always exact.
+    BigInt,
 }
 
-fn execute_assign(mut tree: &mut ParseTree){
+/// What reading a variable that has never been assigned a value does.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Uninitialized {
+    /// Yields 0, as every declared variable starts out as 0.
+    #[default]
+    Zero,
 
-    // <ID> = <EXP>;
+    /// Stops the program with a runtime error.
+    Error,
+}
+
+/// Choices about how a program is evaluated, fixed before it starts running.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Options {
+    pub associativity: Associativity,
+    pub overflow: Overflow,
+    pub uninitialized: Uninitialized,
+
+    /// Reports each statement as it runs, along with what it did, through `Io::trace`.
+    pub trace: bool,
+}
+
+/// The program's memory: every variable and its current value, along with the `Io` that
+/// `read` and `write` statements go through.
+pub(crate) struct Executor<'a> {
+    memory: HashMap<String, Value>,
+    io: &'a mut dyn Io,
+    options: Options,
+}
+
+impl<'a> Executor<'a> {
+    pub(crate) fn new(io: &'a mut dyn Io) -> Executor<'a> {
+        Executor { memory: HashMap::new(), io, options: Options::default() }
+    }
 
-    tree.context.clear(); // New context
+    pub(crate) fn with_options(mut self, options: Options) -> Executor<'a> {
+        self.options = options;
+        self
+    }
 
-    let current_id: String = tree.retrieve_identifier();
+    pub(crate) fn insert_variable(&mut self, identifier: &str, value: Value) {
+        match self.memory.get_mut(identifier) {
+            Some(slot) => *slot = value,
+            None => {
+                self.memory.insert(identifier.to_string(), value);
+            },
+        }
+    }
 
-    execute_id(&mut tree);
-    if tree.get_token().eq(&Token::Assignment) {
-        tree.next(); // throw away =
-        let result: i32 = execute_exp(&mut tree);
-        tree.insert_variable(current_id.clone(), result.clone());
-        if tree.get_token().eq(&Token::Semicolon) {
-            tree.next(); // throw away semicolon
-        } else {
-            panic!("execute_assign: missing ';'");
+    /// Fetches the value of a variable. Variables that have never been stored read as 0.
+    pub(crate) fn retrieve_variable(&self, identifier: &str) -> Value {
+        match self.memory.get(identifier) {
+            Some(value) => value.clone(),
+            None => Value::default(),
         }
-    } else {
-        panic!("execute_assign: expected '='");
     }
 }
 
-fn execute_if(mut tree: &mut ParseTree) {
+/// Reads the whitespace-separated integers of an input file, for use by `read` statements.
+pub fn read_input_file(file: &str) -> Result<Vec<Value>, CoreError> {
+    let buf: Vec<u8> = tokenizer::read_file(file)?;
+    let text: String = String::from_utf8_lossy(&buf).into_owned();
+
+    text.split_whitespace()
+        .map(|word| match word.parse::<Value>() {
+            Ok(value) => Ok(value),
+            Err(..) => Err(CoreError::Io(format!("{}: '{}' is not an integer", file, word))),
+        })
+        .collect()
+}
 
-    // if <COND> then <STMT SEQ> end;
-    // if <COND> then <STMT SEQ> else <STMT SEQ> end;
+pub(crate) fn execute_prog(executor: &mut Executor, program: &Program) -> Result<(), CoreError> {
 
-    tree.next();
-    let mut result: bool = execute_cond(&mut tree);
-    if tree.get_token().eq(&Token::Then) {
-        tree.descend();
-        tree.next();
+    // program <DECL SEQ> begin <STMT SEQ> end
 
-        if result {
-            execute_stmt_seq(&mut tree);
-            let mut nest_count: u32 = 0;
-            while !tree.get_token().eq(&Token::End) || nest_count != 0 {
-                if tree.get_token().eq(&Token::If) || tree.get_token().eq(&Token::While) {
-                    nest_count += 1;
-                }
+    for decl in &program.decl_seq {
+        execute_decl(executor, decl);
+    }
 
-                if tree.get_token().eq(&Token::End) && nest_count != 0 {
-                    nest_count -= 1;
-                }
+    execute_stmt_seq(executor, &program.stmt_seq)
+}
 
-                tree.next();
-            }
-        } else {
-            // println!("Inside else");
-            let mut nest_count: u32 = 0;
-            while !tree.get_token().eq(&Token::Else) && !tree.get_token().eq(&Token::End) || nest_count != 0{
-                if tree.get_token().eq(&Token::If) || tree.get_token().eq(&Token::While) {
-                    nest_count += 1;
-                }
+pub(crate) fn execute_decl(executor: &mut Executor, decl: &Decl) {
 
-                if tree.get_token().eq(&Token::End) && nest_count != 0 {
-                    nest_count -= 1;
-                }
+    // int <ID LIST>;
 
-                tree.next();
-            }
-        }
+    // Variables are left out of memory until they are assigned when reading them is an error.
+    if executor.options.uninitialized == Uninitialized::Error {
+        return;
+    }
 
-        tree.ascend();
-        if tree.get_token().eq(&Token::End) {
-            tree.next();
-            if tree.get_token().eq(&Token::Semicolon) {
-                tree.next();
-            } else {
-                panic!("execute_if: expected ';'");
-            }
-        } else if tree.get_token().eq(&Token::Else) {
-            tree.descend();
-            tree.next();
-            execute_stmt_seq(&mut tree);
-            tree.ascend();
-            if tree.get_token().eq(&Token::End) {
-                tree.next();
-                if tree.get_token().eq(&Token::Semicolon) {
-                    tree.next();
-                } else {
-                    panic!("execute_if: expected ';'");
-                }
-            } else {
-                panic!("execute_if: expected 'end'");
-            }
-        } else {
-            panic!("execute_if: expected 'end' or 'else'");
-        }
-    } else {
-        panic!("execute_if: expected 'then'");
+    for id in &decl.id_list {
+        executor.insert_variable(&id.name, Value::default());
     }
 }
 
-fn execute_loop(mut tree: &mut ParseTree) {
+/// A statement sequence that is partway through execution.
+enum Frame<'a> {
+    /// The remaining statements of a <STMT SEQ>.
+    Block(slice::Iter<'a, Stmt>),
 
-    // while <COND> loop <STMT SEQ> end;
+    /// A while loop whose condition is re-evaluated every time its body finishes.
+    Loop(&'a Stmt, &'a Cond, &'a [Stmt]),
+}
 
-    let mut start_state:u32 = tree.state.clone();
+pub(crate) fn execute_stmt_seq(executor: &mut Executor, stmt_seq: &[Stmt])
+                               -> Result<(), CoreError> {
 
-    tree.next();
-    let mut result: bool = execute_cond(&mut tree);
-    if tree.get_token().eq(&Token::Loop) {
-        tree.next();
-        let loop_depth:u32 = tree.get_depth();
-        if result {
-            tree.descend();
-            execute_stmt_seq(&mut tree);
-            tree.set_state(start_state);
-            execute_loop(&mut tree);
-        } else {
-            let mut nest_count: u32 = 0;
-            while !tree.get_token().eq(&Token::End) || nest_count != 0 {
-                if tree.get_token().eq(&Token::If) || tree.get_token().eq(&Token::While) {
-                    nest_count += 1;
-                }
+    // <STMT>
+    // <STMT> <STMT SEQ>
 
-                if tree.get_token().eq(&Token::End) && nest_count != 0 {
-                    nest_count -= 1;
+    /*
+     * Nested statement sequences and loop iterations are tracked on an explicit stack of
+     * frames rather than by recursing, so the Rust stack does not grow with the number of
+     * times a loop runs or the number of statements in a sequence.
+     */
+    let mut frames: Vec<Frame> = vec![Frame::Block(stmt_seq.iter())];
+
+    while let Some(frame) = frames.pop() {
+        match frame {
+            Frame::Block(mut stmts) => {
+                if let Some(stmt) = stmts.next() {
+                    frames.push(Frame::Block(stmts));
+                    execute_stmt(executor, stmt, &mut frames)?;
                 }
+            },
+            Frame::Loop(stmt, cond, body) => {
 
-                tree.next();
-            }
-            tree.ascend();
-            if tree.get_token().eq(&Token::End) {
-                tree.next();
-                if tree.get_token().eq(&Token::Semicolon) {
-                    tree.next();
-                } else {
-                    panic!("execute_loop: expected ';'");
+                // while <COND> loop <STMT SEQ> end;
+
+                let repeat: bool = execute_cond(executor, cond)?;
+                trace(executor, stmt, &repeat.to_string())?;
+                if repeat {
+                    frames.push(Frame::Loop(stmt, cond, body));
+                    frames.push(Frame::Block(body.iter()));
                 }
-            } else {
-                panic!("execute_loop: expected 'end'");
-            }
+            },
         }
-    } else {
-        panic!("execute_loop: expected 'loop'");
     }
+
+    Ok(())
 }
 
-fn execute_in(mut tree: &mut ParseTree) {
+/// Runs a single statement. Statements that contain a <STMT SEQ> push it onto `frames`
+/// instead of running it directly.
+fn execute_stmt<'a>(executor: &mut Executor, stmt: &'a Stmt, frames: &mut Vec<Frame<'a>>)
+                    -> Result<(), CoreError> {
+    match stmt.kind {
+        StmtKind::Assign(ref id, ref exp) => execute_assign(executor, &id.name, exp)?,
+        StmtKind::If(ref cond, ref then_seq, ref else_seq) => {
+
+            // if <COND> then <STMT SEQ> end;
+            // if <COND> then <STMT SEQ> else <STMT SEQ> end;
+
+            let taken: bool = execute_cond(executor, cond)?;
+            trace(executor, stmt, &taken.to_string())?;
+            if taken {
+                frames.push(Frame::Block(then_seq.iter()));
+            } else if let Some(ref else_seq) = *else_seq {
+                frames.push(Frame::Block(else_seq.iter()));
+            }
+            return Ok(());
+        },
 
-    // read <ID LIST>;
+        // Loops are traced each time their condition is checked.
+        StmtKind::Loop(ref cond, ref stmt_seq) => {
+            frames.push(Frame::Loop(stmt, cond, stmt_seq));
+            return Ok(());
+        },
+        StmtKind::In(ref id_list) => execute_in(executor, id_list)?,
+        StmtKind::Out(ref id_list) => execute_out(executor, id_list)?,
+    }
 
-    tree.context.clear(); // New context
-    tree.next(); // eating the 'read' token
+    // Showing the values the statement stored or wrote.
+    if executor.options.trace {
+        let ids: &[Id] = match stmt.kind {
+            StmtKind::Assign(ref id, _) => slice::from_ref(id),
+            StmtKind::In(ref id_list) | StmtKind::Out(ref id_list) => id_list,
+            _ => &[],
+        };
+        let values: Vec<String> = ids.iter()
+            .map(|id| format!("{} = {}", id.name, executor.retrieve_variable(&id.name)))
+            .collect();
+        trace(executor, stmt, &values.join(", "))?;
+    }
 
-    execute_id_list(&mut tree); // Filling context
+    Ok(())
+}
 
-    // We are actually doing the reading here!
-    for id in tree.context.clone() {
-        let mut found_int: bool = false;
-        let mut val: i32;
+/// Reports a statement that has just run, and what came of it, when tracing is on.
+fn trace(executor: &mut Executor, stmt: &Stmt, outcome: &str) -> Result<(), CoreError> {
+    if !executor.options.trace {
+        return Ok(());
+    }
 
-        print!("{}: ", id.clone());
-        stdout().flush();
-        while !found_int {
-            let mut input_text = String::new();
-            io::stdin()
-                .read_line(&mut input_text)
-                .expect("failed to read from stdin");
+    let line: String = format!("{}:{} {} -> {}", stmt.span.line, stmt.span.column,
+                               parser::print_stmt_head(stmt), outcome);
+    executor.io.trace(&line).map_err(|e| {
+        let message: String = format!("failed to write the trace: {}", e);
+        CoreError::Runtime(Diagnostic::new(&message, stmt.span))
+    })
+}
 
-            let trimmed = input_text.trim();
-            match trimmed.parse::<i32>() {
-                Ok(i) => {
-                    val = i;
-                    tree.insert_variable(id.clone(), val);
-                    found_int = true;
-                },
-                Err(..) => println!("You entered {} but Core expected an integer value.", trimmed)
-            };
+fn execute_assign(executor: &mut Executor, id: &str, exp: &Exp) -> Result<(), CoreError> {
+
+    // <ID> = <EXP>;
+
+    let result: Value = execute_exp(executor, exp)?;
+    executor.insert_variable(id, result);
+
+    Ok(())
+}
+
+fn execute_in(executor: &mut Executor, id_list: &[Id]) -> Result<(), CoreError> {
+
+    // read <ID LIST>;
+
+    for id in id_list {
+        match executor.io.read_int(&id.name) {
+            Ok(Some(value)) => {
+                let value: Value = narrow(executor, value, id.span,
+                                          || format!("the value read for {}", id.name))?;
+                executor.insert_variable(&id.name, value);
+            },
+            Ok(None) => {
+                let message: String = format!("input ended before a value was read for {}",
+                                              id.name);
+                return Err(CoreError::Runtime(
+                    Diagnostic::new(&message, id.span)
+                        .with_hint("supply one integer for every variable that is read")));
+            },
+            Err(e) => {
+                let message: String = format!("failed to read {}: {}", id.name, e);
+                return Err(CoreError::Runtime(Diagnostic::new(&message, id.span)));
+            },
         }
     }
 
-    if tree.get_token().eq(&Token::Semicolon) {
-        tree.next();
-    } else {
-        panic!("execute_in: expected ';'");
-    }
+    Ok(())
 }
 
-fn execute_out(mut tree: &mut ParseTree) {
+fn execute_out(executor: &mut Executor, id_list: &[Id]) -> Result<(), CoreError> {
 
     // write <ID LIST>;
 
-    tree.context.clear(); // New context
-    tree.next();
-
-    execute_id_list(&mut tree);
-    for id in tree.context.clone() {
-        let result: String = tree.retrieve_variable(&id).to_string();
-        println!("{}", result);
+    for id in id_list {
+        let value: Value = read_variable(executor, id)?;
+        if let Err(e) = executor.io.write_int(&value) {
+            let message: String = format!("failed to write {}: {}", id.name, e);
+            return Err(CoreError::Runtime(Diagnostic::new(&message, id.span)));
+        }
     }
 
-    if tree.get_token().eq(&Token::Semicolon) {
-        tree.next();
-    } else {
-        panic!("execute_out: expected ';'");
-    }
+    Ok(())
 }
 
-fn execute_cond(mut tree: &mut ParseTree) -> bool {
+fn execute_cond(executor: &mut Executor, cond: &Cond) -> Result<bool, CoreError> {
 
     // <COMP>
-    // !<COMP>
+    // !<COND>
     // [<COND> && <COND>]
     // [<COND> || <COND>]
 
-    let mut result: bool = false;
-
-    if tree.get_token().eq(&Token::LeftSquare) {
-        tree.next();
-        result = execute_cond(&mut tree);
-        if tree.get_token().eq(&Token::LogicalAnd) {
-            tree.next();
-            let result_and: bool = execute_cond(&mut tree);
-            result = result && result_and;
-            if tree.get_token().eq(&Token::RightSquare) {
-                tree.next();
-            } else {
-                panic!("execute_cond: expected ']'");
-            }
-        } else if tree.get_token().eq(&Token::LogicalOr) {
-            tree.next();
-            let result_or: bool = execute_cond(&mut tree);
-            result = result || result_or;
-            if tree.get_token().eq(&Token::RightSquare) {
-                tree.next();
-            } else {
-                panic!("execute_cond: expected ']'");
-            }
-        } else {
-            panic!("execute_cond: expected '+' or '-'");
-        }
-    } else if tree.get_token().eq(&Token::Exclamation) {
-        result = !execute_comp(&mut tree);
-    } else {
-        result = execute_comp(&mut tree);
-    }
-
-    result
+    Ok(match *cond {
+        Cond::Comp(ref comp) => execute_comp(executor, comp)?,
+        Cond::Not(ref cond) => !execute_cond(executor, cond)?,
+        Cond::And(ref lhs, ref rhs) => {
+            execute_cond(executor, lhs)? && execute_cond(executor, rhs)?
+        },
+        Cond::Or(ref lhs, ref rhs) => {
+            execute_cond(executor, lhs)? || execute_cond(executor, rhs)?
+        },
+    })
 }
 
-fn execute_comp(mut tree: &mut ParseTree) -> bool {
+fn execute_comp(executor: &mut Executor, comp: &Comp) -> Result<bool, CoreError> {
 
     // (<OP> <COMP OP> <OP>)
 
-    let mut result: bool = false;
-    let mut op_a: i32;
-    let mut op_b: i32;
-    let mut cmp: u32;
-
-    if tree.get_token().eq(&Token::LeftParen) {
-        tree.next();
-        op_a = execute_op(&mut tree);
-        cmp = execute_comp_op(&mut tree);
-        op_b = execute_op(&mut tree);
-        if tree.get_token().eq(&Token::RightParen) {
-            tree.next();
-        }
-    } else {
-        panic!("execute_comp: expected '('");
-    }
-
-    match cmp {
-        0 => result = op_a == op_b,
-        1 => result = op_a != op_b,
-        2 => result = op_a <  op_b,
-        3 => result = op_a <= op_b,
-        4 => result = op_a >  op_b,
-        5 => result = op_a >= op_b,
-        _ => panic!("execute_comp: invalid comp_op")
-    }
-
-    result
+    let op_a: Value = execute_op(executor, &comp.lhs)?;
+    let op_b: Value = execute_op(executor, &comp.rhs)?;
+
+    Ok(match comp.comp_op {
+        CompOp::Equal => op_a == op_b,
+        CompOp::NotEqual => op_a != op_b,
+        CompOp::LessThan => op_a < op_b,
+        CompOp::LessThanEqual => op_a <= op_b,
+        CompOp::GreaterThan => op_a > op_b,
+        CompOp::GreaterThanEqual => op_a >= op_b,
+    })
 }
 
-fn execute_exp(mut tree: &mut ParseTree) -> i32 {
+fn execute_exp(executor: &mut Executor, exp: &Exp) -> Result<Value, CoreError> {
 
     // <TRM>
     // <TRM> + <EXP>
     // <TRM> - <EXP>
 
-    let mut result: i32 = 0;
-
-    result = execute_trm(&mut tree);
-
-    if tree.get_token().eq(&Token::Addition) {
-        // handle addition, garble
-        tree.next();
-        result += execute_exp(&mut tree);
-    } else if tree.get_token().eq(&Token::Subtraction) {
-        // handle subtraction
-        tree.next();
-        result -= execute_exp(&mut tree);
+    // Following the chain down its left side collects its terms last to first.
+    let mut trms: Vec<&Trm> = Vec::new();
+    let mut operators: Vec<(Operator, Span)> = Vec::new();
+    let mut rest: &Exp = exp;
+    loop {
+        match *rest {
+            Exp::Trm(ref trm) => {
+                trms.push(trm);
+                break;
+            },
+            Exp::Add(ref lhs, operator_span, ref trm) => {
+                trms.push(trm);
+                operators.push((Operator::Add, operator_span));
+                rest = lhs;
+            },
+            Exp::Sub(ref lhs, operator_span, ref trm) => {
+                trms.push(trm);
+                operators.push((Operator::Sub, operator_span));
+                rest = lhs;
+            },
+        }
     }
+    trms.reverse();
+    operators.reverse();
 
-    result
+    let mut values: Vec<Value> = Vec::new();
+    for trm in trms {
+        values.push(execute_trm(executor, trm)?);
+    }
+    combine(executor, &operators, values, || parser::print_exp(exp))
 }
 
-fn execute_trm(mut tree: &mut ParseTree) -> i32 {
+fn execute_trm(executor: &mut Executor, trm: &Trm) -> Result<Value, CoreError> {
 
     // <OP>
     // <OP> * <TRM>
 
-    let mut result: i32 = execute_op(&mut tree);
+    let mut ops: Vec<&Op> = Vec::new();
+    let mut operators: Vec<(Operator, Span)> = Vec::new();
+    let mut rest: &Trm = trm;
+    while let Trm::Mul(ref lhs, operator_span, ref op) = *rest {
+        ops.push(op);
+        operators.push((Operator::Mul, operator_span));
+        rest = lhs;
+    }
+    if let Trm::Op(ref op) = *rest {
+        ops.push(op);
+    }
+    ops.reverse();
+    operators.reverse();
 
-    if tree.get_token().eq(&Token::Multiplication) {
-        // handle multiplication, garble
-        tree.next();
-        result *= execute_trm(&mut tree);
+    let mut values: Vec<Value> = Vec::new();
+    for op in &ops {
+        values.push(execute_op(executor, op)?);
     }
+    combine(executor, &operators, values, || parser::print_trm(trm))
+}
 
-    result
+/// Combines the values of a chain's operands, where `operators[i]` joins `values[i]` and
+/// `values[i + 1]` and gives the span an overflow is reported at. The parser groups chains from
+/// the left; with `Associativity::Right` they are regrouped from the right here, which is the
+/// only place the two differ.
+fn combine<F: Fn() -> String>(executor: &Executor, operators: &[(Operator, Span)],
+                              values: Vec<Value>, expression: F) -> Result<Value, CoreError> {
+    let mut values = values.into_iter();
+
+    if executor.options.associativity == Associativity::Right {
+        let mut values = values.rev();
+        let mut result: Value = values.next().unwrap_or_default();
+        for (&(operator, span), value) in operators.iter().rev().zip(values) {
+            result = arithmetic(executor, operator, &value, &result, span, &expression)?;
+        }
+        Ok(result)
+    } else {
+        let mut result: Value = values.next().unwrap_or_default();
+        for (&(operator, span), value) in operators.iter().zip(values) {
+            result = arithmetic(executor, operator, &result, &value, span, &expression)?;
+        }
+        Ok(result)
+    }
 }
 
-fn execute_op(mut tree: &mut ParseTree) -> i32 {
+fn execute_op(executor: &mut Executor, op: &Op) -> Result<Value, CoreError> {
 
     // <NO>
     // <ID>
     // (<EXP>)
 
-    let mut result:i32 = 0;
+    match *op {
+        Op::Int(ref value, span) => {
+            narrow(executor, value.clone(), span, || format!("the integer literal {}", value))
+        },
+        Op::Id(ref id) => read_variable(executor, id),
+        Op::Exp(ref exp) => execute_exp(executor, exp),
+    }
+}
 
-    let mut id_flag: bool = false;
-    let mut int_flag: bool = false;
+/// Fetches the value of a variable the program refers to, which is an error in
+/// `Uninitialized::Error` mode if the variable has not been assigned.
+fn read_variable(executor: &Executor, id: &Id) -> Result<Value, CoreError> {
+    match executor.memory.get(&id.name) {
+        Some(value) => Ok(value.clone()),
+        None if executor.options.uninitialized == Uninitialized::Error => {
+            let message: String = format!("{} is read before it is assigned a value", id.name);
+            let hint: String = format!("assign {} or read it from input first", id.name);
+            Err(CoreError::Runtime(Diagnostic::new(&message, id.span).with_hint(&hint)))
+        },
+        None => Ok(Value::default()),
+    }
+}
 
-    if tree.get_token().eq(&Token::LeftParen) {
-        tree.next(); // left paren
-        result = execute_exp(&mut tree);
-        if tree.get_token().eq(&Token::RightParen) {
-            tree.next();
-        } else {
-            panic!("execute_op: missing ')'");
-        }
-    } else {
-        match tree.get_token() {
-            &Token::Identifier(ref id) => id_flag = true,
-            &Token::Integer(ref i) => int_flag = true,
-            _ => panic!("execute_op: token is not identifier")
-        }
+#[derive(Debug, Clone, Copy)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+}
 
-        if id_flag {
-            id_flag = false;
-            let current_id: String = tree.retrieve_identifier().clone();
-            result = tree.retrieve_variable(&current_id);
-            execute_id(&mut tree);
-        } else if int_flag {
-            int_flag = false;
-            result = *tree.retrieve_integer();
-            execute_int(&mut tree);
-        } else {
-            panic!("execute_op: invalid argument")
-        }
+const OVERFLOW_HINT: &str = "run with --wrapping or --saturating to allow overflow, or with \
+                             --bigint for exact arithmetic";
+
+/// Applies `operator`, handling a result that does not fit in 32 bits as the executor's
+/// `Overflow` option says. `expression` names the source expression in the error.
+fn arithmetic<F: FnOnce() -> String>(executor: &Executor, operator: Operator, lhs: &Value,
+                                     rhs: &Value, span: Span, expression: F)
+                                     -> Result<Value, CoreError> {
+    if executor.options.overflow == Overflow::BigInt {
+        return Ok(match operator {
+            Operator::Add => lhs.add(rhs),
+            Operator::Sub => lhs.sub(rhs),
+            Operator::Mul => lhs.mul(rhs),
+        });
     }
 
-    result
+    // Every value has already been narrowed to 32 bits outside of big-integer mode.
+    let (lhs, rhs) = (lhs.wrapping_i32(), rhs.wrapping_i32());
+    let (result, symbol) = match (executor.options.overflow, operator) {
+        (Overflow::Wrapping, Operator::Add) => (Some(lhs.wrapping_add(rhs)), "+"),
+        (Overflow::Wrapping, Operator::Sub) => (Some(lhs.wrapping_sub(rhs)), "-"),
+        (Overflow::Wrapping, Operator::Mul) => (Some(lhs.wrapping_mul(rhs)), "*"),
+        (Overflow::Saturating, Operator::Add) => (Some(lhs.saturating_add(rhs)), "+"),
+        (Overflow::Saturating, Operator::Sub) => (Some(lhs.saturating_sub(rhs)), "-"),
+        (Overflow::Saturating, Operator::Mul) => (Some(lhs.saturating_mul(rhs)), "*"),
+        (_, Operator::Add) => (lhs.checked_add(rhs), "+"),
+        (_, Operator::Sub) => (lhs.checked_sub(rhs), "-"),
+        (_, Operator::Mul) => (lhs.checked_mul(rhs), "*"),
+    };
+
+    result.map(Value::from).ok_or_else(|| {
+        let message: String = format!("arithmetic overflow in `{}`: {} {} {} does not fit in 32 bits",
+                                      expression(), lhs, symbol, rhs);
+        CoreError::Runtime(Diagnostic::new(&message, span).with_hint(OVERFLOW_HINT))
+    })
 }
 
-fn execute_comp_op(mut tree: &mut ParseTree) -> u32 {
+/// Brings a value from outside the program's arithmetic, a literal or a value that was read,
+/// into the range the executor's `Overflow` option allows. `describe` names it in the error.
+fn narrow<F: FnOnce() -> String>(executor: &Executor, value: Value, span: Span, describe: F)
+                                 -> Result<Value, CoreError> {
+    match executor.options.overflow {
+        Overflow::BigInt => Ok(value),
+        Overflow::Wrapping => Ok(Value::from(value.wrapping_i32())),
+        Overflow::Saturating => Ok(Value::from(value.saturating_i32())),
+        Overflow::Checked => match value.to_i32() {
+            Some(..) => Ok(value),
+            None => {
+                let message: String = format!("{} does not fit in 32 bits", describe());
+                Err(CoreError::Runtime(Diagnostic::new(&message, span).with_hint(OVERFLOW_HINT)))
+            },
+        },
+    }
+}
 
-    // This is like a literal "opcode" we will be using
-    // to perform the actual comparisons later on
-    let mut result: u32 = 0;
+#[cfg(test)]
+mod test {
+    use tokenizer::{Token, Lexeme};
+    use parser;
+    use super::*;
 
-    match tree.get_token() {
-        &Token::LogicalEquality => {
-            result = 0;
-        },
-        &Token::LogicalInequality => {
-            result = 1;
-        },
-        &Token::LessThan => {
-            result = 2;
-        },
-        &Token::LessThanEqual => {
-            result = 3;
-        },
-        &Token::GreaterThan => {
-            result = 4;
-        },
-        &Token::GreaterThanEqual => {
-            result = 5;
-        },
-        _ => panic!("execute_comp_op: unexpected comp op")
+    fn id(name: &str) -> Token {
+        Token::Identifier(name.to_string())
     }
 
-    tree.next();
+    fn int(value: i32) -> Token {
+        Token::Integer(Value::from(value))
+    }
 
-    result
-}
+    fn parse(tokens: Vec<Token>) -> Program {
+        let lexemes: Vec<Lexeme> = tokens.into_iter()
+            .map(|token| Lexeme::new(token, Span::default()))
+            .collect();
+        parser::init_parser(lexemes).unwrap()
+    }
 
-fn execute_id(mut tree: &mut ParseTree) {
-    let identifier: String = tree.retrieve_identifier();
-    tree.context.push(identifier.clone());
-    if !tree.memory.contains_key(&identifier.clone()) {
-        tree.insert_variable(identifier.clone(), 0);
+    #[test]
+    fn takes_nested_if_inside_else_branch() {
+        // program int X, Y; begin
+        //     if (X == 1) then Y = 1;
+        //     else if (X == 0) then Y = 2; else Y = 3; end; X = 5; end;
+        // end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            Token::If, Token::LeftParen, id("X"), Token::LogicalEquality, int(1),
+            Token::RightParen, Token::Then,
+            id("Y"), Token::Assignment, int(1), Token::Semicolon,
+            Token::Else,
+            Token::If, Token::LeftParen, id("X"), Token::LogicalEquality, int(0),
+            Token::RightParen, Token::Then,
+            id("Y"), Token::Assignment, int(2), Token::Semicolon,
+            Token::Else,
+            id("Y"), Token::Assignment, int(3), Token::Semicolon,
+            Token::End, Token::Semicolon,
+            id("X"), Token::Assignment, int(5), Token::Semicolon,
+            Token::End, Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let mut io = BufferIo::default();
+        let mut executor = Executor::new(&mut io);
+        execute_prog(&mut executor, &parse(tokens)).unwrap();
+
+        assert_eq!(executor.retrieve_variable("X"), 5);
+        assert_eq!(executor.retrieve_variable("Y"), 2);
+    }
+
+    #[test]
+    fn runs_long_loops_without_overflowing_the_stack() {
+        // program int N, C; begin N = 1000000; while (N > 0) loop C = C + 1; N = N - 1;
+        // end; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("N"), Token::Comma, id("C"), Token::Semicolon,
+            Token::Begin,
+            id("N"), Token::Assignment, int(1000000), Token::Semicolon,
+            Token::While, Token::LeftParen, id("N"), Token::GreaterThan, int(0),
+            Token::RightParen, Token::Loop,
+            id("C"), Token::Assignment, id("C"), Token::Addition, int(1),
+            Token::Semicolon,
+            id("N"), Token::Assignment, id("N"), Token::Subtraction, int(1),
+            Token::Semicolon,
+            Token::End, Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let mut io = BufferIo::default();
+        let mut executor = Executor::new(&mut io);
+        execute_prog(&mut executor, &parse(tokens)).unwrap();
+
+        assert_eq!(executor.retrieve_variable("N"), 0);
+        assert_eq!(executor.retrieve_variable("C"), 1000000);
+    }
+
+    #[test]
+    fn reads_from_supplied_input_until_exhausted() {
+        // program int X, Y; begin read X, Y; Y = X + Y; read X; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            Token::Read, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            id("Y"), Token::Assignment, id("X"), Token::Addition, id("Y"), Token::Semicolon,
+            Token::Read, id("X"), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let mut io = IteratorIo::new(vec![9, 36]);
+        let mut executor = Executor::new(&mut io);
+        let result = execute_prog(&mut executor, &parse(tokens));
+
+        assert_eq!(executor.retrieve_variable("Y"), 45);
+        assert_eq!(result.unwrap_err().message(), "input ended before a value was read for X");
+    }
+
+    #[test]
+    fn captures_written_values_in_buffer() {
+        // program int X, Y; begin read X; Y = X * 2; write X, Y; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            Token::Read, id("X"), Token::Semicolon,
+            id("Y"), Token::Assignment, id("X"), Token::Multiplication, int(2),
+            Token::Semicolon,
+            Token::Write, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let mut io = BufferIo::new(vec![21]);
+        execute_prog(&mut Executor::new(&mut io), &parse(tokens)).unwrap();
+
+        assert_eq!(io.output, vec![21, 42]);
+        assert!(io.input.is_empty());
+    }
+
+    #[test]
+    fn groups_chains_from_the_left_unless_asked_otherwise() {
+        // program int X, Y; begin X = 10 - 3 - 2; Y = 2 * 3 - 4 + 5; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, int(10), Token::Subtraction,
+            int(3), Token::Subtraction, int(2), Token::Semicolon,
+            id("Y"), Token::Assignment, int(2), Token::Multiplication,
+            int(3), Token::Subtraction, int(4), Token::Addition,
+            int(5), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+        let program: Program = parse(tokens);
+
+        let mut io = BufferIo::default();
+        let mut executor = Executor::new(&mut io);
+        execute_prog(&mut executor, &program).unwrap();
+        assert_eq!(executor.retrieve_variable("X"), 5);
+        assert_eq!(executor.retrieve_variable("Y"), 7);
+
+        let options: Options = Options { associativity: Associativity::Right, ..Options::default() };
+        let mut executor = Executor::new(&mut io).with_options(options);
+        execute_prog(&mut executor, &program).unwrap();
+        assert_eq!(executor.retrieve_variable("X"), 9);
+        assert_eq!(executor.retrieve_variable("Y"), -3);
     }
-    tree.next();
-}
 
-fn execute_int(mut tree: &mut ParseTree) {
-    let integer: i32 = *tree.retrieve_integer();
-    tree.next();
+    #[test]
+    fn handles_overflow_as_the_options_say() {
+        // program int X; begin X = 2147483647; X = X + 1 - 1; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, int(2147483647), Token::Semicolon,
+            id("X"), Token::Assignment, id("X"), Token::Addition, int(1),
+            Token::Subtraction, int(1), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+        let program: Program = parse(tokens);
+        let mut io = BufferIo::default();
+
+        let error: CoreError = execute_prog(&mut Executor::new(&mut io), &program).unwrap_err();
+        assert_eq!(error.message(),
+                   "arithmetic overflow in `X + 1 - 1`: 2147483647 + 1 does not fit in 32 bits");
+
+        let options: Options = Options { overflow: Overflow::Wrapping, ..Options::default() };
+        let mut executor = Executor::new(&mut io).with_options(options);
+        execute_prog(&mut executor, &program).unwrap();
+        assert_eq!(executor.retrieve_variable("X"), 2147483647);
+
+        let options: Options = Options { overflow: Overflow::Saturating, ..Options::default() };
+        let mut executor = Executor::new(&mut io).with_options(options);
+        execute_prog(&mut executor, &program).unwrap();
+        assert_eq!(executor.retrieve_variable("X"), 2147483646);
+    }
+
+    #[test]
+    fn keeps_values_of_any_size_in_bigint_mode() {
+        // program int X; begin X = 99999999999 * 99999999999 - 1; write X; end
+        let huge: Token = Token::Integer("99999999999".parse().unwrap());
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, huge.clone(), Token::Multiplication, huge,
+            Token::Subtraction, int(1), Token::Semicolon,
+            Token::Write, id("X"), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+        let program: Program = parse(tokens);
+        let mut io = BufferIo::default();
+
+        let error: CoreError = execute_prog(&mut Executor::new(&mut io), &program).unwrap_err();
+        assert_eq!(error.message(), "the integer literal 99999999999 does not fit in 32 bits");
+
+        let options: Options = Options { overflow: Overflow::BigInt, ..Options::default() };
+        execute_prog(&mut Executor::new(&mut io).with_options(options), &program).unwrap();
+        assert_eq!(io.output[0].to_string(), "9999999999800000000000");
+    }
+
+    #[test]
+    fn reading_unassigned_variable_is_an_error_in_strict_mode() {
+        // program int X, Y; begin Y = X + 1; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            id("Y"), Token::Assignment, id("X"), Token::Addition, int(1), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+        let program: Program = parse(tokens);
+        let mut io = BufferIo::default();
+
+        let mut executor = Executor::new(&mut io);
+        execute_prog(&mut executor, &program).unwrap();
+        assert_eq!(executor.retrieve_variable("Y"), 1);
+
+        let options: Options = Options {
+            uninitialized: Uninitialized::Error,
+            ..Options::default()
+        };
+        let error: CoreError = execute_prog(&mut Executor::new(&mut io).with_options(options),
+                                            &program).unwrap_err();
+        assert_eq!(error.message(), "X is read before it is assigned a value");
+    }
 }
@@ -0,0 +1,97 @@
+//! The formatter rewrites Core source in the pretty-printer's canonical layout, carrying the
+//! source's comments across.
+
+use error::CoreError;
+use tokenizer;
+use tokenizer::{Lexeme, Trivia};
+use parser;
+use parser::{Line, Program};
+
+/// Formats Core source text, indenting nested statements by `indent` spaces per level.
+pub fn format_source(source: &[u8], indent: usize) -> Result<String, Vec<CoreError>> {
+    let tokens: Vec<Lexeme> = tokenizer::tokenize_with_trivia(source, false)?;
+    let program: Program = parser::init_parser(tokens.clone())?;
+
+    /*
+     * The pretty-printer emits the program's tokens in source order, so each line it prints
+     * stands for the next run of source tokens. Comments leading that run are placed on lines
+     * of their own above it, and comments inside it or trailing its tokens trail it.
+     */
+    let mut output: String = String::new();
+    let mut cursor: usize = 0;
+
+    for line in parser::print_lines(&program) {
+        let run: &[Lexeme] = &tokens[cursor..cursor + line.tokens];
+        cursor += run.len();
+
+        // Comments just before `begin`, `else` or `end` belong with the block above them.
+        let depth: usize = if closes_block(&line) { line.depth + 1 } else { line.depth };
+        for comment in comments(&run[0].leading) {
+            let leading: Line = Line { depth, text: comment_text(source, comment), tokens: 0 };
+            output.push_str(&leading.indent(indent));
+            output.push('\n');
+        }
+
+        output.push_str(&line.indent(indent));
+        for (index, lexeme) in run.iter().enumerate() {
+            let inner: &[Trivia] = if index == 0 { &[] } else { &lexeme.leading };
+            for comment in comments(inner).chain(comments(&lexeme.trailing)) {
+                output.push(' ');
+                output.push_str(&comment_text(source, comment));
+            }
+        }
+        output.push('\n');
+    }
+
+    // Whatever is left comes after the program's final `end`.
+    for comment in comments(&tokens[cursor].leading) {
+        output.push_str(&comment_text(source, comment));
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+fn comments(trivia: &[Trivia]) -> impl Iterator<Item = &Trivia> {
+    trivia.iter().filter(|trivia| trivia.is_comment())
+}
+
+fn closes_block(line: &Line) -> bool {
+    line.text == "begin" || line.text == "else" || line.text.starts_with("end")
+}
+
+/// The source text of a comment, with any line endings normalized.
+fn comment_text(source: &[u8], comment: &Trivia) -> String {
+    String::from_utf8_lossy(comment.text(source)).replace("\r\n", "\n").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_source;
+
+    #[test]
+    fn keeps_comments_beside_the_code_they_annotate() {
+        let source: &[u8] = b"// Counts down.\r\n\
+                              program int X; begin\r\n\
+                              \tread X;   // how many\r\n\
+                              \twhile (X > 0) loop X = X - 1; /* step */\r\n\
+                              \t\t// nothing else\r\n\
+                              \tend; write X;\r\n\
+                              end\r\n";
+        let expected: &str = "// Counts down.\n\
+                              program\n  \
+                                int X;\n\
+                              begin\n  \
+                                read X; // how many\n  \
+                                while (X > 0) loop\n    \
+                                  X = X - 1; /* step */\n    \
+                                  // nothing else\n  \
+                                end;\n  \
+                                write X;\n\
+                              end\n";
+
+        let formatted: String = format_source(source, 2).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(formatted.as_bytes(), 2).unwrap(), expected);
+    }
+}
@@ -0,0 +1,391 @@
+//! Golden-file tests: runs the test programs under a directory and compares what they print with
+//! the output recorded beside them.
+//!
+//! Two kinds of test are discovered in each directory:
+//!
+//! * `<name>` with `<name>expected` holds the course's token codes for `<name>`, as printed by
+//!   `core tokens`.
+//! * `<name>.core` is run once for each `<name>input<suffix>` beside it, or once with no input if
+//!   there are none, and its output compared with `<name>expected<suffix>`.
+//!
+//! A program's output is each value it writes, one per line, followed by any errors it stops
+//! with. Programs with no expected output are reported as ignored.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use error::CoreError;
+use diagnostic;
+use executor;
+use executor::BufferIo;
+use tokenizer;
+use tokenizer::TokenFormat;
+use value::Value;
+use {check, run};
+
+/// Lines of unchanged text shown around each change in a diff.
+const CONTEXT: usize = 3;
+
+/// The most entries the table behind a diff may have, which bounds the memory it takes.
+const MAX_TABLE_SIZE: usize = 1 << 20;
+
+/// A single golden-file test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    /// The source file, relative to the directory the tests were discovered in.
+    pub name: String,
+    pub kind: CaseKind,
+    pub source: PathBuf,
+    pub input: Option<PathBuf>,
+    pub expected: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseKind {
+    Tokens,
+    Program,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The output differed from what was expected, as shown by the unified diff.
+    Failed(String),
+    Ignored,
+}
+
+/// How many tests had each outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+}
+
+impl Case {
+    /// Describes the test in reports, e.g. `program4.core < program4inputA`.
+    pub fn title(&self) -> String {
+        match (self.kind, self.input.as_ref()) {
+            (CaseKind::Tokens, _) => format!("{} (tokens)", self.name),
+            (CaseKind::Program, Some(input)) => format!("{} < {}", self.name, file_name(input)),
+            (CaseKind::Program, None) => self.name.clone(),
+        }
+    }
+
+    /// Runs the test, comparing its output with the expected output.
+    pub fn run(&self) -> Result<Outcome, CoreError> {
+        let expected_file: &Path = match self.expected {
+            Some(ref expected_file) => expected_file,
+            None => return Ok(Outcome::Ignored),
+        };
+
+        let expected: String = read_text(expected_file)?.replace("\r\n", "\n");
+        let actual: String = self.output()?;
+        if actual == expected {
+            return Ok(Outcome::Passed);
+        }
+
+        let expected_name: String = file_name(expected_file);
+        let mut diff: String = unified_diff(&expected, &actual, &expected_name, "actual");
+        if !diff.contains("\n@@ ") {
+            diff.push_str("(the outputs differ only in their final newline)\n");
+        }
+
+        Ok(Outcome::Failed(diff))
+    }
+
+    /// Runs the test's source, returning what it prints.
+    pub fn output(&self) -> Result<String, CoreError> {
+        let source: Vec<u8> = tokenizer::read_file(&self.source.to_string_lossy())?;
+        let file: String = file_name(&self.source);
+
+        if self.kind == CaseKind::Tokens {
+            return Ok(tokenizer::list_tokens(&source, TokenFormat::Codes).0);
+        }
+
+        let input: Vec<Value> = match self.input {
+            Some(ref input) => executor::read_input_file(&input.to_string_lossy())?,
+            None => Vec::new(),
+        };
+
+        let mut io: BufferIo = BufferIo::new(input);
+        let result: Result<(), Vec<CoreError>> = check(&source)
+            .and_then(|checked| run(&checked.program, &mut io).map_err(|error| vec![error]));
+
+        let mut output: String = io.output.iter().map(|value| format!("{}\n", value)).collect();
+        if let Err(errors) = result {
+            for error in errors {
+                output.push_str(&diagnostic::render(&error, &file, &source));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Finds every test under `dir`, in order of their paths.
+pub fn discover(dir: &Path) -> Result<Vec<Case>, CoreError> {
+    let mut cases: Vec<Case> = Vec::new();
+    discover_in(dir, dir, &mut cases)?;
+
+    Ok(cases)
+}
+
+fn discover_in(root: &Path, dir: &Path, cases: &mut Vec<Case>) -> Result<(), CoreError> {
+    let mut files: BTreeSet<String> = BTreeSet::new();
+    let mut subdirs: BTreeSet<PathBuf> = BTreeSet::new();
+
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        let path: PathBuf = entry.map_err(|e| io_error(dir, e))?.path();
+        if path.is_dir() {
+            subdirs.insert(path);
+        } else {
+            files.insert(file_name(&path));
+        }
+    }
+
+    let existing = |name: String| if files.contains(&name) { Some(dir.join(name)) } else { None };
+    let name = |file: &str| -> String {
+        let path: PathBuf = dir.join(file);
+        path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/")
+    };
+
+    for file in &files {
+        if let Some(stem) = file.strip_suffix(".core") {
+            let prefix: String = format!("{}input", stem);
+            let suffixes: Vec<&str> = files.iter()
+                .filter_map(|other| other.strip_prefix(prefix.as_str()))
+                .collect();
+
+            let inputs: Vec<Option<&str>> = if suffixes.is_empty() {
+                vec![None]
+            } else {
+                suffixes.into_iter().map(Some).collect()
+            };
+
+            for suffix in inputs {
+                cases.push(Case {
+                    name: name(file),
+                    kind: CaseKind::Program,
+                    source: dir.join(file),
+                    input: suffix.map(|suffix| dir.join(format!("{}{}", prefix, suffix))),
+                    expected: existing(format!("{}expected{}", stem, suffix.unwrap_or(""))),
+                });
+            }
+        } else if let Some(expected) = existing(format!("{}expected", file)) {
+            cases.push(Case {
+                name: name(file),
+                kind: CaseKind::Tokens,
+                source: dir.join(file),
+                input: None,
+                expected: Some(expected),
+            });
+        }
+    }
+
+    for subdir in subdirs {
+        discover_in(root, &subdir, cases)?;
+    }
+
+    Ok(())
+}
+
+/// Runs every test under `dir`, writing a report of the results to `out`.
+pub fn run_tests(dir: &Path, out: &mut dyn Write) -> Result<Summary, CoreError> {
+    let cases: Vec<Case> = discover(dir)?;
+    let mut summary: Summary = Summary::default();
+    let mut failures: Vec<(String, String)> = Vec::new();
+
+    let report = |error: std::io::Error| CoreError::Io(format!("writing the report: {}", error));
+    let plural: &str = if cases.len() == 1 { "" } else { "s" };
+    writeln!(out, "running {} test{}", cases.len(), plural).map_err(report)?;
+
+    for case in &cases {
+        let status: &str = match case.run() {
+            Ok(Outcome::Passed) => {
+                summary.passed += 1;
+                "ok"
+            },
+            Ok(Outcome::Ignored) => {
+                summary.ignored += 1;
+                "ignored, no expected output"
+            },
+            Ok(Outcome::Failed(diff)) => {
+                summary.failed += 1;
+                failures.push((case.title(), diff));
+                "FAILED"
+            },
+            Err(error) => {
+                summary.failed += 1;
+                failures.push((case.title(), format!("{}\n", error)));
+                "FAILED"
+            },
+        };
+        writeln!(out, "test {} ... {}", case.title(), status).map_err(report)?;
+    }
+
+    if !failures.is_empty() {
+        writeln!(out, "\nfailures:").map_err(report)?;
+        for (title, details) in failures {
+            write!(out, "\n---- {} ----\n{}", title, details).map_err(report)?;
+        }
+    }
+
+    let result: &str = if summary.failed == 0 { "ok" } else { "FAILED" };
+    writeln!(out, "\ntest result: {}. {} passed; {} failed; {} ignored",
+             result, summary.passed, summary.failed, summary.ignored).map_err(report)?;
+
+    Ok(summary)
+}
+
+/// A line of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// Compares two texts line by line, in the format of `diff -u`.
+pub fn unified_diff(expected: &str, actual: &str, expected_name: &str, actual_name: &str)
+                    -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // The lines both texts start and end with are kept without comparing them any further, so
+    // only the part in between needs the table in `middle_edits`.
+    let prefix: usize = old.iter().zip(&new).take_while(|&(a, b)| a == b).count();
+    let suffix: usize = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|&(a, b)| a == b)
+        .count();
+
+    // Each edit, with the lines of `old` and `new` it comes before.
+    let mut edits: Vec<(Edit, usize, usize)> = (0..prefix).map(|k| (Edit::Keep, k, k)).collect();
+    let middle: Vec<(Edit, usize, usize)> = middle_edits(&old[prefix..old.len() - suffix],
+                                                         &new[prefix..new.len() - suffix]);
+    edits.extend(middle.into_iter().map(|(edit, i, j)| (edit, i + prefix, j + prefix)));
+    edits.extend((0..suffix).map(|k| {
+        (Edit::Keep, old.len() - suffix + k, new.len() - suffix + k)
+    }));
+
+    let mut output: String = format!("--- {}\n+++ {}\n", expected_name, actual_name);
+    let changes: Vec<usize> = (0..edits.len()).filter(|&k| edits[k].0 != Edit::Keep).collect();
+    let mut index: usize = 0;
+
+    while index < changes.len() {
+        // Changes close enough to share context go in one hunk.
+        let start: usize = changes[index].saturating_sub(CONTEXT);
+        let mut last: usize = changes[index];
+        while index + 1 < changes.len() && changes[index + 1] - last <= 2 * CONTEXT + 1 {
+            index += 1;
+            last = changes[index];
+        }
+        let end: usize = (last + CONTEXT + 1).min(edits.len());
+        index += 1;
+
+        let hunk: &[(Edit, usize, usize)] = &edits[start..end];
+        let old_count: usize = hunk.iter().filter(|edit| edit.0 != Edit::Insert).count();
+        let new_count: usize = hunk.iter().filter(|edit| edit.0 != Edit::Delete).count();
+        let old_start: usize = if old_count == 0 { hunk[0].1 } else { hunk[0].1 + 1 };
+        let new_start: usize = if new_count == 0 { hunk[0].2 } else { hunk[0].2 + 1 };
+
+        output.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start,
+                                 new_count));
+        for &(edit, i, j) in hunk {
+            let line: String = match edit {
+                Edit::Keep => format!(" {}\n", old[i]),
+                Edit::Delete => format!("-{}\n", old[i]),
+                Edit::Insert => format!("+{}\n", new[j]),
+            };
+            output.push_str(&line);
+        }
+    }
+
+    output
+}
+
+/// The edits that turn `old` into `new`, keeping a longest common subsequence of their lines.
+/// When the table for that would be too large, every line of `old` is deleted and every line of
+/// `new` inserted instead.
+fn middle_edits(old: &[&str], new: &[&str]) -> Vec<(Edit, usize, usize)> {
+    if old.len().saturating_mul(new.len()) > MAX_TABLE_SIZE {
+        let deletes = (0..old.len()).map(|i| (Edit::Delete, i, 0));
+        let inserts = (0..new.len()).map(|j| (Edit::Insert, old.len(), j));
+        return deletes.chain(inserts).collect();
+    }
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut lcs: Vec<Vec<usize>> = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits: Vec<(Edit, usize, usize)> = Vec::new();
+    let (mut i, mut j): (usize, usize) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((Edit::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push((Edit::Delete, i, j));
+            i += 1;
+        } else {
+            edits.push((Edit::Insert, i, j));
+            j += 1;
+        }
+    }
+
+    edits
+}
+
+fn read_text(path: &Path) -> Result<String, CoreError> {
+    let buf: Vec<u8> = tokenizer::read_file(&path.to_string_lossy())?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn file_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> CoreError {
+    CoreError::Io(format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod test {
+    use super::unified_diff;
+
+    #[test]
+    fn diffs_in_unified_format() {
+        let expected: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let actual: &str = "1\n2\n3\n4\n5\n6\n70\n8\n9\n10\n11\n12\n13\n";
+
+        assert_eq!(unified_diff(expected, actual, "expected", "actual"),
+                   "--- expected\n+++ actual\n\
+                    @@ -4,9 +4,10 @@\n 4\n 5\n 6\n-7\n+70\n 8\n 9\n 10\n 11\n 12\n+13\n");
+        assert_eq!(unified_diff("a\n", "b\n", "x", "y"), "--- x\n+++ y\n@@ -1,1 +1,1 @@\n-a\n+b\n");
+        assert_eq!(unified_diff("a\n", "a\n", "x", "y"), "--- x\n+++ y\n");
+    }
+
+    #[test]
+    fn diffs_long_outputs_without_a_quadratic_table() {
+        let expected: String = (0..5000).map(|n| format!("{}\n", n)).collect();
+        let actual: String = (0..5000).map(|n| format!("{}\n", n + 1)).collect();
+
+        let diff: String = unified_diff(&expected, &actual, "x", "y");
+        assert!(diff.starts_with("--- x\n+++ y\n@@ -1,5000 +1,5000 @@\n-0\n-1\n"));
+        assert!(diff.ends_with("+4999\n+5000\n"));
+    }
+}
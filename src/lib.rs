@@ -0,0 +1,142 @@
+//! An interpreter for the Core language.
+//!
+//! Source text goes through `tokenize`, `parse` and `check` to become a `Program`, which `run`
+//! then executes against any `Io`.
+//!
+//! ```
+//! use core_interpreter::{BufferIo, CoreError};
+//!
+//! let source: &[u8] = b"program int X; begin read X; X = X * 2; write X; end";
+//! let checked = core_interpreter::check(source).map_err(|errors| errors[0].clone())?;
+//! assert!(checked.warnings.is_empty());
+//!
+//! let mut io = BufferIo::new(vec![21]);
+//! core_interpreter::run(&checked.program, &mut io)?;
+//! assert_eq!(io.output, vec![42]);
+//! # Ok::<(), CoreError>(())
+//! ```
+
+pub mod error;
+pub mod diagnostic;
+pub mod tokenizer;
+pub mod parser;
+mod checker;
+mod formatter;
+#[doc(hidden)]
+pub mod golden;
+#[doc(hidden)]
+pub mod repl;
+pub mod executor;
+pub mod value;
+
+pub use error::{CoreError, Diagnostic, Position, Severity};
+pub use tokenizer::{Token, Lexeme, LexicalError, Span, Trivia, TriviaKind};
+pub use parser::Program;
+pub use executor::{Io, ConsoleIo, IteratorIo, BufferIo};
+pub use executor::{Options, Associativity, Overflow, Uninitialized};
+pub use value::Value;
+
+use executor::Executor;
+
+/// Converts Core source text into tokens, reporting every illegal token found.
+pub fn tokenize(source: &[u8]) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    tokenizer::tokenize(source)
+}
+
+/// Parses Core source text into a `Program`, reporting every syntax error found.
+pub fn parse(source: &[u8]) -> Result<Program, Vec<CoreError>> {
+    let lexemes: Vec<Lexeme> = tokenize(source)?;
+    parser::init_parser(lexemes)
+}
+
+/// A program that passed `check`, along with the warnings raised for it.
+#[derive(Debug, Clone)]
+pub struct Checked {
+    pub program: Program,
+    pub warnings: Vec<CoreError>,
+}
+
+/// Parses Core source text and verifies it is a legal program, ready to `run`. When it is not,
+/// every error is returned along with any warnings.
+pub fn check(source: &[u8]) -> Result<Checked, Vec<CoreError>> {
+    let program: Program = parse(source)?;
+    let (warnings, errors): (Vec<CoreError>, Vec<CoreError>) = checker::check_prog(&program)
+        .into_iter()
+        .partition(CoreError::is_warning);
+
+    if errors.is_empty() {
+        Ok(Checked { program, warnings })
+    } else {
+        Err(errors.into_iter().chain(warnings).collect())
+    }
+}
+
+/// Rewrites Core source text in canonical form, indenting nested statements by `indent` spaces
+/// per level and keeping its comments.
+pub fn format(source: &[u8], indent: usize) -> Result<String, Vec<CoreError>> {
+    formatter::format_source(source, indent)
+}
+
+/// Executes a checked program, with `read` and `write` statements going through `io`.
+pub fn run(program: &Program, io: &mut dyn Io) -> Result<(), CoreError> {
+    run_with_options(program, io, Options::default())
+}
+
+/// Executes a checked program like `run`, evaluating it as `options` say.
+pub fn run_with_options(program: &Program, io: &mut dyn Io, options: Options)
+                        -> Result<(), CoreError> {
+    executor::execute_prog(&mut Executor::new(io).with_options(options), program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_syntax_errors_from_source_text() {
+        let errors: Vec<CoreError> = parse(b"program int X; begin X = ; end").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position(), Some(Position { line: 1, column: 26 }));
+    }
+
+    #[test]
+    fn keeps_parsing_past_a_stray_else_or_end() {
+        for &source in &[&b"program int X; begin X = 1; else X = ; write X; end"[..],
+                         &b"program int X; begin X = 1; end; X = ; end"[..]] {
+            let messages: Vec<String> = parse(source).unwrap_err().iter()
+                .map(|error| error.message().to_string())
+                .collect();
+
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[1], "expected integer, identifier or '(', found ';'");
+        }
+    }
+
+    #[test]
+    fn reports_overflow_at_the_operator_or_literal() {
+        let program: Program = parse(b"program int X; begin X = 2147483647; X = 1 + X + 1; end")
+            .unwrap();
+        let error: CoreError = run(&program, &mut BufferIo::default()).unwrap_err();
+
+        assert_eq!(error.position(), Some(Position { line: 1, column: 44 }));
+
+        let program: Program = parse(b"program int X; begin X = 1 + 99999999999; end").unwrap();
+        let error: CoreError = run(&program, &mut BufferIo::default()).unwrap_err();
+
+        assert_eq!(error.position(), Some(Position { line: 1, column: 30 }));
+    }
+
+    #[test]
+    fn runs_with_the_options_given() {
+        let program: Program = parse(b"program int X; begin X = 10 - 3 - 2; write X; end").unwrap();
+        let options: Options = Options {
+            associativity: Associativity::Right,
+            ..Options::default()
+        };
+        let mut io: BufferIo = BufferIo::default();
+        run_with_options(&program, &mut io, options).unwrap();
+
+        assert_eq!(io.output, vec![Value::from(9)]);
+    }
+}
@@ -1,18 +1,211 @@
+extern crate core_interpreter;
+
+mod cli;
+
 use std::env;
-mod tokenizer;
-mod executor;
-mod parser;
+use std::io;
+use std::path::Path;
+use std::process;
+use core_interpreter::{golden, repl, CoreError};
+use core_interpreter::golden::Summary;
+use core_interpreter::tokenizer::TokenFormat;
+use core_interpreter::{Associativity, Overflow, Uninitialized, Options};
+use core_interpreter::value::Value;
+use cli::Source;
+
+/// The subcommands, each named by the first argument that is not an option.
+const COMMANDS: [&str; 7] = ["run", "tokens", "parse", "check", "fmt", "repl", "test"];
+
+/// The exit status for a command line the interpreter does not understand.
+const USAGE_ERROR: i32 = 2;
 
 fn main() {
-    // Fetching the arguments to get the input file name.
-    let args: Vec<String> = env::args().collect();
+    // Fetching the arguments, leaving out the name the interpreter was called by.
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    if take_flag(&mut args, "--help") || take_flag(&mut args, "-h") {
+        print!("{}", cli::help());
+        return;
+    }
+
+    if take_flag(&mut args, "--version") || take_flag(&mut args, "-V") {
+        println!("core {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
+    // Global options, which may appear anywhere on the command line.
+    let input_file: Option<String> = take_value(&mut args, "--input");
+    let options: Options = match take_options(&mut args) {
+        Some(options) => options,
+        None => usage_error(),
+    };
+
+    // `./core <core-source-file-name>` is short for `./core run <core-source-file-name>`, and
+    // likewise for `-` and `-e <source>`.
+    let command: String = match args.first() {
+        Some(arg) if COMMANDS.contains(&arg.as_str()) => args.remove(0),
+        _ => "run".to_string(),
+    };
+
+    let result: Result<(), Vec<CoreError>> = match command.as_str() {
+        "run" => cli::init_driver(&single_source(args), input_file.as_deref(), options),
+        "tokens" => print_tokens(args),
+        "parse" => cli::init_parse_printer(&single_source(args)),
+        "check" => cli::init_checker(&single_source(args)),
+        "fmt" => format_file(args),
+        "repl" => start_repl(args, input_file.as_deref(), options),
+        _ => run_golden_tests(args),
+    };
+
+    if let Err(errors) = result {
+        process::exit(exit_code(&errors));
+    }
+}
+
+/// Pulls the options that choose how a program is evaluated out of `args`, or returns `None`
+/// when they contradict one another.
+fn take_options(args: &mut Vec<String>) -> Option<Options> {
+    let mut options: Options = Options::default();
 
-    // Testing the arguments to make sure the interpreter is being called correctly.
-    if !tokenizer::is_valid_input(args.len()) {
-        tokenizer::print_usage();
+    // `--right-assoc` groups `-` and `*` chains from the right, as earlier releases did.
+    if take_flag(args, "--right-assoc") {
+        options.associativity = Associativity::Right;
+    }
+
+    // `--strict-init` makes reading a variable before it is assigned an error rather than 0.
+    if take_flag(args, "--strict-init") {
+        options.uninitialized = Uninitialized::Error;
+    }
+
+    // `--trace` reports each statement on stderr as it runs.
+    options.trace = take_flag(args, "--trace");
+
+    // Values that overflow 32 bits are an error unless one of these is given.
+    let mut overflow_modes: Vec<Overflow> = Vec::new();
+    if take_flag(args, "--wrapping") {
+        overflow_modes.push(Overflow::Wrapping);
+    }
+    if take_flag(args, "--saturating") {
+        overflow_modes.push(Overflow::Saturating);
+    }
+    if take_flag(args, "--bigint") {
+        overflow_modes.push(Overflow::BigInt);
+    }
+    if overflow_modes.len() > 1 {
+        return None;
+    }
+    if let Some(&overflow) = overflow_modes.first() {
+        options.overflow = overflow;
+    }
+
+    Some(options)
+}
+
+/// Handles `./core tokens [--names | --json] <core-source-file-name>`.
+fn print_tokens(mut args: Vec<String>) -> Result<(), Vec<CoreError>> {
+    let format: TokenFormat = match (take_flag(&mut args, "--names"),
+                                     take_flag(&mut args, "--json")) {
+        (false, false) => TokenFormat::Codes,
+        (true, false) => TokenFormat::Names,
+        (false, true) => TokenFormat::Json,
+        (true, true) => usage_error(),
+    };
+
+    cli::init_token_printer(&single_source(args), format)
+}
+
+/// Handles `./core fmt [--check] [--indent <width>] <core-source-file-name>`.
+fn format_file(mut args: Vec<String>) -> Result<(), Vec<CoreError>> {
+    let check: bool = take_flag(&mut args, "--check");
+    let indent: usize = match take_value(&mut args, "--indent") {
+        Some(width) => width.parse().unwrap_or_else(|_| usage_error()),
+        None => 4,
+    };
+
+    if !cli::init_formatter(&single_source(args), check, indent)? {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handles `./core repl [options]`.
+fn start_repl(args: Vec<String>, input_file: Option<&str>, options: Options)
+              -> Result<(), Vec<CoreError>> {
+    if !args.is_empty() {
+        usage_error();
+    }
+
+    let input: Option<Vec<Value>> = cli::read_input(input_file)?;
+
+    cli::reported(repl::init_repl(input, options).map_err(|error| vec![error]), "<repl>", &[])
+}
+
+/// Handles `./core test <test-directory>`.
+fn run_golden_tests(args: Vec<String>) -> Result<(), Vec<CoreError>> {
+    let directory: String = single_file(&args);
+
+    let summary: Result<Summary, Vec<CoreError>> =
+        golden::run_tests(Path::new(&directory), &mut io::stdout()).map_err(|error| vec![error]);
+
+    if cli::reported(summary, &directory, &[])?.failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// The program named by what is left in `args` once its options are taken out: `-e <source>`,
+/// `-` for standard input, or a path.
+fn single_source(mut args: Vec<String>) -> Source {
+    match take_value(&mut args, "-e") {
+        Some(text) if args.is_empty() => Source::Inline(text),
+        Some(..) => usage_error(),
+        None => Source::from_arg(&single_file(&args)),
+    }
+}
+
+/// The one path left in `args` once its options are taken out. Anything else is a usage error.
+fn single_file(args: &[String]) -> String {
+    match *args {
+        [ref file] if file == "-" || !file.starts_with('-') => file.to_string(),
+        _ => usage_error(),
+    }
+}
+
+/// The exit status for a failed command: that of its first error, as warnings never fail one.
+fn exit_code(errors: &[CoreError]) -> i32 {
+    match errors.iter().find(|error| !error.is_warning()) {
+        Some(error) => error.exit_code(),
+        None => 1,
+    }
+}
+
+/// Prints how to invoke the interpreter and exits with the usage error status.
+fn usage_error() -> ! {
+    eprint!("{}", cli::usage());
+    eprintln!("Try './core --help' for more information.");
+    process::exit(USAGE_ERROR);
+}
+
+/// Removes `flag` and the value after it from `args`, returning the value. A flag with no
+/// value after it is left in place.
+fn take_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index: usize = args.iter().position(|arg| arg == flag)?;
+    if index + 1 < args.len() {
+        let value: String = args.remove(index + 1);
+        args.remove(index);
+        Some(value)
     } else {
-        // If so, we will begin parsing the input file.
-        let ref file: String = args[1];
-        tokenizer::init_driver(file);
+        None
+    }
+}
+
+/// Removes `flag` from `args`, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => {
+            args.remove(index);
+            true
+        },
+        None => false,
     }
 }
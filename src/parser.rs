@@ -1,628 +1,1102 @@
 #![allow(dead_code)]
-#![allow(unused_mut)]
-#![allow(unused_variables)]
-#![allow(unused_assignments)]
 
-use std::ops::Index;
-use tokenizer::Token;
-use std::collections::HashMap;
+//! The parser consumes the tokenizer's output and builds an abstract syntax tree of the Core
+//! program, which the executor and the pretty-printer walk.
 
+use tokenizer::{Token, Lexeme, Span};
+use error::{CoreError, Diagnostic, Position};
+use value::Value;
+
+/// program <DECL SEQ> begin <STMT SEQ> end
+#[derive(Debug, PartialEq, Clone)]
+pub struct Program {
+    pub decl_seq: Vec<Decl>,
+    pub stmt_seq: Vec<Stmt>,
+}
+
+/// A part of a program entered on its own, as at the REPL.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Entry {
+    /// <DECL SEQ>
+    Decls(Vec<Decl>),
+
+    /// <STMT SEQ>
+    Stmts(Vec<Stmt>),
+}
+
+/// int <ID LIST>;
+#[derive(Debug, PartialEq, Clone)]
+pub struct Decl {
+    pub id_list: Vec<Id>,
+}
+
+/// An identifier, along with the span it was read from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Id {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A statement, along with the span of its first token.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StmtKind {
+    /// <ID> = <EXP>;
+    Assign(Id, Exp),
+
+    /// if <COND> then <STMT SEQ> end;
+    /// if <COND> then <STMT SEQ> else <STMT SEQ> end;
+    If(Cond, Vec<Stmt>, Option<Vec<Stmt>>),
+
+    /// while <COND> loop <STMT SEQ> end;
+    Loop(Cond, Vec<Stmt>),
+
+    /// read <ID LIST>;
+    In(Vec<Id>),
+
+    /// write <ID LIST>;
+    Out(Vec<Id>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Cond {
+    /// <COMP>
+    Comp(Comp),
+
+    /// !<COND>
+    Not(Box<Cond>),
+
+    /// [<COND> && <COND>]
+    And(Box<Cond>, Box<Cond>),
+
+    /// [<COND> || <COND>]
+    Or(Box<Cond>, Box<Cond>),
+}
+
+/// (<OP> <COMP OP> <OP>)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comp {
+    pub lhs: Op,
+    pub comp_op: CompOp,
+    pub rhs: Op,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompOp {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEqual,
+    GreaterThan,
+    GreaterThanEqual,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Exp {
+    /// <TRM>
+    Trm(Trm),
+
+    /// <EXP> + <TRM>, so that chains group from the left. The span is that of the `+`.
+    Add(Box<Exp>, Span, Trm),
+
+    /// <EXP> - <TRM>
+    Sub(Box<Exp>, Span, Trm),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Trm {
+    /// <OP>
+    Op(Op),
+
+    /// <TRM> * <OP>, so that chains group from the left. The span is that of the `*`.
+    Mul(Box<Trm>, Span, Op),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Op {
+    /// <NO>, along with the span of the literal
+    Int(Value, Span),
+
+    /// <ID>
+    Id(Id),
+
+    /// (<EXP>)
+    Exp(Box<Exp>),
+}
+
+/// A cursor over the tokenizer's output, along with the syntax errors recovered from so far.
 #[derive(Clone)]
-pub struct ParseTree {
-    pub tokens: Vec<Token>,
-    pub memory: HashMap<String, i32>,
-    pub current_statement: String,
-    pub statements: Vec<String>,
-    pub context: Vec<String>,
-    pub state: u32,
-    pub depth: u32
+struct ParseTree {
+    lexemes: Vec<Lexeme>,
+    state: usize,
+    errors: Vec<CoreError>,
 }
 
 impl ParseTree {
+    pub fn new(lexemes: Vec<Lexeme>) -> ParseTree {
+        ParseTree { lexemes, state: 0, errors: Vec::new() }
+    }
+
+    /// Moves on to the next token. The cursor never advances past the final `EOF` token.
     pub fn next(&mut self) {
-        self.state += 1;
+        if self.state + 1 < self.lexemes.len() {
+            self.state += 1;
+        }
     }
 
-    pub fn get_token(&mut self) -> &Token {
-        return &self.tokens.index(self.state as usize);
+    pub fn get_token(&self) -> &Token {
+        match self.lexemes.get(self.state) {
+            Some(lexeme) => &lexeme.token,
+            None => &Token::EOF,
+        }
     }
 
-    pub fn retrieve_identifier(&mut self) -> String {
+    /// Whether the current token is an 'else', or an 'end' with another 'end' after it: one
+    /// the top-level statements stopped at that cannot be the program's final 'end'.
+    fn at_stray_block_end(&self) -> bool {
         match *self.get_token() {
-            Token::Identifier(ref id) => return id.to_string(),
-            _ => {
-                panic!("ParseTree.retrieve_identifier: token is not identifier");
-            }
+            Token::Else => true,
+            Token::End => self.lexemes[self.state + 1..].iter()
+                .any(|lexeme| lexeme.token == Token::End),
+            _ => false,
+        }
+    }
+
+    /// The span of the current token in the source file.
+    pub fn span(&self) -> Span {
+        match self.lexemes.get(self.state) {
+            Some(lexeme) => lexeme.span,
+            None => Span::default(),
         }
     }
 
-    // pub fn read_stdin(&mut self) -> i32 {
-    //     // return self.input_stream.remove(0);
-    // }
+    /// The position of the current token in the source file.
+    pub fn position(&self) -> Position {
+        self.span().position()
+    }
+
+    /// Builds a syntax error located at the current token, which was not one of `expected`.
+    pub fn error(&self, expected: &str, hint: &str) -> CoreError {
+        let message: String = format!("expected {}, found {}", expected,
+                                      self.get_token().describe());
+        CoreError::Syntax(Diagnostic::new(&message, self.span()).with_hint(hint))
+    }
 
-    pub fn retrieve_integer(&mut self) -> &i32 {
+    pub fn retrieve_identifier(&self) -> Result<String, CoreError> {
         match *self.get_token() {
-            Token::Integer(ref value) => return value,
-            _ => panic!("ParseTree.retrieve_integer: token is not integer")
+            Token::Identifier(ref id) => Ok(id.to_string()),
+            _ => Err(self.error("identifier",
+                                "identifiers are uppercase letters followed by digits, e.g. X1")),
         }
     }
 
-    pub fn push_statement(&mut self, statement: String) {
-        for i in 0..(self.depth * 4) {
-            print!(" ");
+    pub fn retrieve_integer(&self) -> Result<Value, CoreError> {
+        match *self.get_token() {
+            Token::Integer(ref value) => Ok(value.clone()),
+            _ => Err(self.error("integer", "integers are written as digits, e.g. 42")),
         }
-        println!("{}", statement);
-        self.statements.push(statement);
     }
 
-    pub fn fetch_current_statement(&mut self) {
-        for i in 0..(self.depth * 4) {
-            print!(" ");
+    /// Consumes the current token if it matches `token`, failing with `hint` otherwise.
+    fn expect(&mut self, token: Token, hint: &str) -> Result<(), CoreError> {
+        if self.get_token().eq(&token) {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(&token.describe(), hint))
         }
-        println!("{}", self.current_statement);
-        self.statements.push(self.current_statement.to_owned());
-        self.current_statement = "".to_string();
     }
 
-    pub fn insert_variable(&mut self, identifier: String, value: i32) {
-        self.memory.insert(identifier, value);
+    /// Whether the current token is one panic-mode recovery stops at.
+    fn at_synchronizing_token(&self) -> bool {
+        matches!(*self.get_token(),
+                 Token::Semicolon | Token::End | Token::Begin | Token::Else | Token::EOF)
     }
 
-    pub fn retrieve_variable(&mut self, identifier: &str) -> i32 {
-        match self.memory.get(identifier) {
-            Some(&value) => return value,
-            _ => panic!("retrieve_variable: variable not present in HashMap"),
+    /// Records `error`, unless it is another one at the end of the file: once the tokens run
+    /// out, everything the grammar still expects is missing, and one error says so.
+    fn push_error(&mut self, error: CoreError) {
+        let position: Option<Position> = Some(self.position());
+        if self.get_token().eq(&Token::EOF)
+            && self.errors.last().is_some_and(|last| last.position() == position) {
+            return;
         }
+
+        self.errors.push(error);
     }
 
-    pub fn display_variables(&mut self) {
-        println!("\nVariables updated. Shown below.");
-        for (identifier, value) in &self.memory {
-            println!("{}: {}", identifier, value);
+    /// Records `error` and skips ahead to a point where parsing can resume: just past the next
+    /// ';', or at the next 'end', 'begin' or 'else'.
+    fn recover(&mut self, error: CoreError) {
+        self.push_error(error);
+
+        while !self.at_synchronizing_token() {
+            self.next();
         }
-        println!("");
-    }
 
-    pub fn set_state(&mut self, state: u32) {
-        self.state = state;
+        if self.get_token().eq(&Token::Semicolon) {
+            self.next();
+        }
     }
 
-    pub fn get_depth(&mut self) -> u32 {
-        self.depth
-    }
+    /// Skips ahead to `token` without crossing a synchronizing token, returning whether it was
+    /// found. Used to resume inside a statement whose header was malformed.
+    fn skip_to(&mut self, token: &Token) -> bool {
+        while !self.get_token().eq(token) && !self.at_synchronizing_token() {
+            self.next();
+        }
 
-    pub fn descend(&mut self) {
-        self.depth += 1;
+        self.get_token().eq(token)
     }
+}
+
+/// Parses a full token stream (terminated by `Token::EOF`) into a `Program`, or returns every
+/// syntax error found along the way.
+pub fn init_parser(lexemes: Vec<Lexeme>) -> Result<Program, Vec<CoreError>> {
+    let mut tree = ParseTree::new(lexemes);
+    let program: Program = parse_prog(&mut tree);
 
-    pub fn ascend(&mut self) {
-        self.depth -= 1;
+    if tree.errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(tree.errors)
     }
 }
 
-pub fn init_parser(file_tokens: Vec<Token>, stdin: Vec<i32>) {
-    let mut this_parse_tree = ParseTree {
-        tokens: file_tokens.clone(),
-        memory: HashMap::new(),
-        current_statement: "".to_string(),
-        statements: Vec::new(),
-        context: Vec::new(),
-        state: 0,
-        depth: 0
+/// Parses a token stream holding either a <DECL SEQ> or a <STMT SEQ> on its own, or returns
+/// every syntax error found along the way.
+pub fn init_entry_parser(lexemes: Vec<Lexeme>) -> Result<Entry, Vec<CoreError>> {
+    let mut tree = ParseTree::new(lexemes);
+    let entry: Entry = if tree.get_token().eq(&Token::Int) {
+        Entry::Decls(parse_decl_seq(&mut tree))
+    } else {
+        Entry::Stmts(parse_stmt_seq(&mut tree))
     };
 
-    parse_prog(&mut this_parse_tree);
+    if tree.errors.is_empty() && !tree.get_token().eq(&Token::EOF) {
+        let error = tree.error("end of input",
+                               "enter either declarations or statements, not both at once");
+        tree.push_error(error);
+    }
 
-    /*
-     * For writing tests later:
-     *
-     * println!("{}", this_parse_tree.get_token());
-     * edit_tree(&mut this_parse_tree);
-     * println!("{}", this_parse_tree.get_token());
-     * println!("{}", this_parse_tree.retrieve_variable("testing"));
-     * println!("{}", this_parse_tree.retrieve_variable("should_not_work"));
-     */
+    if tree.errors.is_empty() {
+        Ok(entry)
+    } else {
+        Err(tree.errors)
+    }
 }
 
-fn parse_prog(mut tree: &mut ParseTree) {
+fn parse_prog(tree: &mut ParseTree) -> Program {
 
     // program <DECL SEQ> begin <STMT SEQ> end
 
-    if tree.get_token().eq(&Token::Program) {
-        tree.push_statement("program".to_string());
-        tree.descend();
-        tree.next(); // Consume the 'program' keyword
-        parse_decl_seq(&mut tree);
-        tree.ascend();
+    if let Err(error) = tree.expect(Token::Program, "every Core program starts with 'program'") {
+        // Carry on as though 'program' were there.
+        tree.push_error(error);
+    }
+
+    let decl_seq: Vec<Decl> = parse_decl_seq(tree);
+
+    if let Err(error) = tree.expect(Token::Begin, "declarations must be followed by 'begin'") {
+        tree.recover(error);
         if tree.get_token().eq(&Token::Begin) {
-            tree.push_statement("begin".to_string());
-            tree.descend();
-            tree.next(); // Consume the 'begin' keyword
-            parse_stmt_seq(&mut tree);
-            if tree.get_token().eq(&Token::End) {
-                tree.ascend();
-                tree.push_statement("end".to_string());
-            } else {
-                panic!("parse_prog: expected 'end'");
-            }
+            tree.next();
+        }
+    }
+
+    let mut stmt_seq: Vec<Stmt> = parse_stmt_seq(tree);
+
+    /*
+     * The statements stop at the first 'else' or 'end' they cannot use. Unless that is the
+     * program's final 'end', it is a stray: report it and carry on with the statements after it,
+     * so their errors are reported too.
+     */
+    while tree.at_stray_block_end() {
+        let error = if tree.get_token().eq(&Token::Else) {
+            tree.error("statement", "this 'else' has no 'if' to belong to")
         } else {
-            panic!("parse_prog: expected 'begin'");
+            tree.error("statement", "this 'end' has no 'if' or 'while' to close")
+        };
+        tree.push_error(error);
+
+        tree.next();
+        if tree.get_token().eq(&Token::Semicolon) {
+            tree.next();
         }
-    } else {
-        panic!("program @ {} : {}", tree.state, tree.tokens[tree.state as usize]);
+        if !matches!(*tree.get_token(), Token::End | Token::Else | Token::EOF) {
+            stmt_seq.extend(parse_stmt_seq(tree));
+        }
+    }
+
+    if let Err(error) = tree.expect(Token::End,
+                                    "the program's statements must be followed by 'end'") {
+        tree.push_error(error);
+    } else if !tree.get_token().eq(&Token::EOF) {
+        let error = tree.error("end of file", "nothing may follow the program's final 'end'");
+        tree.push_error(error);
     }
+
+    Program { decl_seq, stmt_seq }
 }
 
-fn parse_decl_seq(mut tree: &mut ParseTree) {
+fn parse_decl_seq(tree: &mut ParseTree) -> Vec<Decl> {
 
     // <DECL>
     // <DECL> <DECL SEQ>
 
-    parse_decl(&mut tree); // parses declaration and moves on
+    let mut decl_seq: Vec<Decl> = Vec::new();
 
-    if tree.get_token().eq(&Token::Int) {
-        parse_decl_seq(&mut tree);
+    loop {
+        match parse_decl(tree) {
+            Ok(decl) => decl_seq.push(decl),
+            Err(error) => tree.recover(error),
+        }
+
+        if !tree.get_token().eq(&Token::Int) {
+            break;
+        }
     }
+
+    decl_seq
 }
 
-fn parse_decl(mut tree: &mut ParseTree) {
+fn parse_decl(tree: &mut ParseTree) -> Result<Decl, CoreError> {
 
     // int <ID LIST>;
 
-    if tree.get_token().eq(&Token::Int) {
-        tree.current_statement.push_str("int ");
-        tree.next(); // consume int
-        parse_id_list(&mut tree);
-        if tree.get_token().eq(&Token::Semicolon) {
-            tree.current_statement.push(';');
-            tree.fetch_current_statement();
-            tree.next(); // an entire declaration has been consumed; move on
-        } else {
-            panic!("parse_decl: expected ';'");
-        }
-    } else {
-        panic!("parse_decl: expected 'int'");
-    }
+    tree.expect(Token::Int, "variables must be declared with 'int' before 'begin'")?;
+    let id_list: Vec<Id> = parse_id_list(tree)?;
+    tree.expect(Token::Semicolon, "missing ';' after declaration")?;
+
+    Ok(Decl { id_list })
 }
 
-fn parse_stmt_seq(mut tree: &mut ParseTree) {
+fn parse_stmt_seq(tree: &mut ParseTree) -> Vec<Stmt> {
 
     // <STMT>
     // <STMT> <STMT SEQ>
 
-    let mut id_flag: bool = false;
-    let mut match_flag: bool = false;
-
-    match tree.get_token() {
-        &Token::Identifier(ref i) => {
-            id_flag = true;
-            match_flag = true;
-        },
-        _ => print!("")
-    }
-
-    if tree.get_token().eq(&Token::Read) {
-        match_flag = true;
-        parse_in(&mut tree);
-    } else if tree.get_token().eq(&Token::Write) {
-        match_flag = true;
-        parse_out(&mut tree);
-    } else if tree.get_token().eq(&Token::If) {
-        match_flag = true;
-        parse_if(&mut tree);
-    } else if tree.get_token().eq(&Token::While) {
-        match_flag = true;
-        parse_loop(&mut tree);
-    }
-
-    if id_flag {
-        parse_assign(&mut tree);
+    let mut stmt_seq: Vec<Stmt> = Vec::new();
+    let start: usize = tree.state;
+
+    loop {
+        let span: Span = tree.span();
+        let result: Result<StmtKind, CoreError> = match *tree.get_token() {
+            Token::Identifier(_) => parse_assign(tree),
+            Token::Read => parse_in(tree),
+            Token::Write => parse_out(tree),
+            Token::If => parse_if(tree),
+            Token::While => parse_loop(tree),
+            Token::End | Token::Else | Token::EOF => {
+                // A sequence needs at least one statement, though one that failed to parse
+                // has already been reported.
+                if tree.state == start {
+                    let error = tree.error("statement",
+                                           "every statement sequence needs at least one \
+                                            statement");
+                    tree.push_error(error);
+                }
+                break;
+            },
+            _ => {
+                let error = tree.error("statement",
+                                       "statements start with an identifier, 'read', 'write', \
+                                        'if' or 'while'");
+                tree.next(); // always make progress past the stray token
+                Err(error)
+            },
+        };
+
+        match result {
+            Ok(kind) => stmt_seq.push(Stmt { kind, span }),
+            Err(error) => tree.recover(error),
+        }
     }
 
-    if match_flag {
-        parse_stmt_seq(&mut tree);
-    }
+    stmt_seq
 }
 
-fn parse_id_list(mut tree: &mut ParseTree) {
+fn parse_id_list(tree: &mut ParseTree) -> Result<Vec<Id>, CoreError> {
 
     // <ID>
     // <ID>, <ID LIST>
 
-    let mut identifier: String;
-    let mut id_result: (String, ParseTree);
-    parse_id(&mut tree);
+    let mut id_list: Vec<Id> = vec![parse_id(tree)?];
 
-    if tree.get_token().eq(&Token::Comma) {
-        tree.current_statement.push_str(", ");
+    while tree.get_token().eq(&Token::Comma) {
         tree.next(); // consume comma
-        parse_id_list(&mut tree);
+        id_list.push(parse_id(tree)?);
     }
+
+    Ok(id_list)
 }
 
-fn parse_assign(mut tree: &mut ParseTree){
+fn parse_assign(tree: &mut ParseTree) -> Result<StmtKind, CoreError> {
 
     // <ID> = <EXP>;
 
-    tree.context.clear(); // New context
-
-    let current_id: String = tree.retrieve_identifier();
+    let id: Id = parse_id(tree)?;
+    tree.expect(Token::Assignment, "assignments are written as <ID> = <EXP>;")?;
+    let exp: Exp = parse_exp(tree)?;
+    tree.expect(Token::Semicolon, "missing ';' after assignment")?;
 
-    parse_id(&mut tree);
-    if tree.get_token().eq(&Token::Assignment) {
-        tree.current_statement.push_str(" = ");
-        tree.next(); // throw away =
-        let result: i32 = parse_exp(&mut tree);
-        tree.insert_variable(current_id.clone(), result.clone());
-        // tree.output_stream.push(format!("{} = {}", current_id, result.to_string()));
-        if tree.get_token().eq(&Token::Semicolon) {
-            tree.current_statement.push(';');
-            tree.fetch_current_statement();
-            tree.next(); // throw away semicolon
-        } else {
-            panic!("parse_assign: missing ';'");
-        }
-    } else {
-        panic!("parse_assign: expected '='");
-    }
+    Ok(StmtKind::Assign(id, exp))
 }
 
-fn parse_if(mut tree: &mut ParseTree) {
+fn parse_if(tree: &mut ParseTree) -> Result<StmtKind, CoreError> {
 
     // if <COND> then <STMT SEQ> end;
     // if <COND> then <STMT SEQ> else <STMT SEQ> end;
 
-    tree.current_statement.push_str("if ");
-    tree.next();
-    parse_cond(&mut tree);
-    if tree.get_token().eq(&Token::Then) {
-        tree.current_statement.push_str(" then");
-        tree.fetch_current_statement();
-        tree.descend();
+    tree.next(); // consume 'if'
+    let cond: Cond = parse_header_cond(tree, &Token::Then)?;
+    tree.expect(Token::Then, "missing 'then' after the if condition")?;
+    let then_seq: Vec<Stmt> = parse_stmt_seq(tree);
+
+    let else_seq: Option<Vec<Stmt>> = if tree.get_token().eq(&Token::Else) {
         tree.next();
-        parse_stmt_seq(&mut tree);
-        tree.ascend();
-        if tree.get_token().eq(&Token::End) {
-            tree.current_statement.push_str("end");
-            tree.next();
-            if tree.get_token().eq(&Token::Semicolon) {
-                tree.current_statement.push_str(";");
-                tree.fetch_current_statement();
-                tree.next();
-            } else {
-                panic!("parse_if: expected ';'");
-            }
-        } else if tree.get_token().eq(&Token::Else) {
-            tree.current_statement.push_str("else");
-            tree.fetch_current_statement();
-            tree.descend();
-            tree.next();
-            parse_stmt_seq(&mut tree);
-            tree.ascend();
-            if tree.get_token().eq(&Token::End) {
-                tree.current_statement.push_str("end");
-                tree.next();
-                if tree.get_token().eq(&Token::Semicolon) {
-                    tree.current_statement.push_str(";");
-                    tree.fetch_current_statement();
-                    tree.next();
-                } else {
-                    panic!("parse_if: expected ';'");
-                }
-            } else {
-                panic!("parse_if: expected 'end'");
-            }
-        } else {
-            panic!("parse_if: expected 'end' or 'else'");
-        }
+        let else_seq: Vec<Stmt> = parse_stmt_seq(tree);
+        tree.expect(Token::End, "if statements are closed with 'end;'")?;
+        Some(else_seq)
+    } else if tree.get_token().eq(&Token::End) {
+        tree.next();
+        None
     } else {
-        panic!("parse_if: expected 'then'");
-    }
+        return Err(tree.error("'else' or 'end'", "if statements are closed with 'end;'"));
+    };
+    tree.expect(Token::Semicolon, "missing ';' after the 'end' of an if statement")?;
+
+    Ok(StmtKind::If(cond, then_seq, else_seq))
 }
 
-fn parse_loop(mut tree: &mut ParseTree) {
+fn parse_loop(tree: &mut ParseTree) -> Result<StmtKind, CoreError> {
 
     // while <COND> loop <STMT SEQ> end;
 
-    tree.current_statement.push_str("while ");
-    tree.next();
-    let result: bool = parse_cond(&mut tree);
-    if tree.get_token().eq(&Token::Loop) {
-        tree.current_statement.push_str(" loop");
-        tree.fetch_current_statement();
-        tree.next();
-        tree.descend();
-        parse_stmt_seq(&mut tree);
-        tree.ascend();
-        if tree.get_token().eq(&Token::End) {
-            tree.current_statement.push_str("end");
-            tree.next();
-            if tree.get_token().eq(&Token::Semicolon) {
-                tree.current_statement.push_str(";");
-                tree.fetch_current_statement();
-                tree.next();
-            } else {
-                panic!("parse_loop: expected ';'");
-            }
-        } else {
-            panic!("parse_loop: expected 'end'");
-        }
-    } else {
-        panic!("parse_loop: expected 'loop'");
-    }
+    tree.next(); // consume 'while'
+    let cond: Cond = parse_header_cond(tree, &Token::Loop)?;
+    tree.expect(Token::Loop, "missing 'loop' after the while condition")?;
+    let stmt_seq: Vec<Stmt> = parse_stmt_seq(tree);
+    tree.expect(Token::End, "while loops are closed with 'end;'")?;
+    tree.expect(Token::Semicolon, "missing ';' after the 'end' of a while loop")?;
+
+    Ok(StmtKind::Loop(cond, stmt_seq))
 }
 
-fn parse_in(mut tree: &mut ParseTree) {
+fn parse_in(tree: &mut ParseTree) -> Result<StmtKind, CoreError> {
 
     // read <ID LIST>;
 
-    tree.context.clear(); // New context
-    tree.current_statement.push_str("read ");
     tree.next(); // eating the 'read' token
+    let id_list: Vec<Id> = parse_id_list(tree)?;
+    tree.expect(Token::Semicolon, "missing ';' after read statement")?;
 
-    parse_id_list(&mut tree); // Filling context
-
-    // We are actually doing the reading here!
-    // for id in tree.context.clone() {
-    //     // let val: i32 = tree.read_stdin();
-    //     // tree.insert_variable(id.clone(), val.clone());
-    //     // tree.output_stream.push(format!("{} = {}", id, val));
-    // }
-
-    if tree.get_token().eq(&Token::Semicolon) {
-        tree.current_statement.push(';');
-        tree.fetch_current_statement();
-        tree.next();
-    } else {
-        panic!("parse_in: expected ';'");
-    }
+    Ok(StmtKind::In(id_list))
 }
 
-fn parse_out(mut tree: &mut ParseTree) {
+fn parse_out(tree: &mut ParseTree) -> Result<StmtKind, CoreError> {
 
     // write <ID LIST>;
 
-    tree.context.clear(); // New context
-    tree.current_statement.push_str("write ");
-    tree.next();
+    tree.next(); // eating the 'write' token
+    let id_list: Vec<Id> = parse_id_list(tree)?;
+    tree.expect(Token::Semicolon, "missing ';' after write statement")?;
 
-    parse_id_list(&mut tree);
-    // for id in tree.context.clone() {
-    //     let result: String = tree.retrieve_variable(&id).to_string();
-    //     tree.output_stream.push(format!("{} = {}", id, result));
-    // }
+    Ok(StmtKind::Out(id_list))
+}
 
-    if tree.get_token().eq(&Token::Semicolon) {
-        tree.current_statement.push(';');
-        tree.fetch_current_statement();
-        tree.next();
-    } else {
-        panic!("parse_out: expected ';'");
+/// Parses the condition of an if or while statement. If the condition is malformed but the
+/// `keyword` ending it can still be found, the error is recorded and parsing resumes with the
+/// statement's body, so that errors inside the body are reported too.
+fn parse_header_cond(tree: &mut ParseTree, keyword: &Token) -> Result<Cond, CoreError> {
+    match parse_cond(tree) {
+        Ok(cond) => Ok(cond),
+        Err(error) => {
+            if !tree.skip_to(keyword) {
+                return Err(error);
+            }
+            tree.push_error(error);
+
+            // The program will not be run, so any condition will do as a placeholder.
+            Ok(Cond::Comp(Comp {
+                lhs: Op::Int(Value::from(0), Span::default()),
+                comp_op: CompOp::Equal,
+                rhs: Op::Int(Value::from(0), Span::default()),
+            }))
+        },
     }
 }
 
-fn parse_cond(mut tree: &mut ParseTree) -> bool {
+fn parse_cond(tree: &mut ParseTree) -> Result<Cond, CoreError> {
 
     // <COMP>
-    // !<COMP>
+    // !<COND>
     // [<COND> && <COND>]
     // [<COND> || <COND>]
 
-    let mut result: bool = false;
-
     if tree.get_token().eq(&Token::LeftSquare) {
-        tree.current_statement.push('[');
         tree.next();
-        result = parse_cond(&mut tree);
-        if tree.get_token().eq(&Token::LogicalAnd) {
-            tree.current_statement.push_str(" && ");
-            tree.next();
-            let result_and: bool = parse_cond(&mut tree);
-            result = result && result_and;
-            if tree.get_token().eq(&Token::RightSquare) {
-                tree.current_statement.push(']');
-                tree.next();
-            } else {
-                panic!("parse_cond: expected ']'");
-            }
-        } else if tree.get_token().eq(&Token::LogicalOr) {
-            tree.current_statement.push_str(" || ");
-            tree.next();
-            let result_or: bool = parse_cond(&mut tree);
-            result = result || result_or;
-            if tree.get_token().eq(&Token::RightSquare) {
-                tree.current_statement.push(']');
-                tree.next();
-            } else {
-                panic!("parse_cond: expected ']'");
-            }
+        let lhs: Cond = parse_cond(tree)?;
+        let is_and: bool = match *tree.get_token() {
+            Token::LogicalAnd => true,
+            Token::LogicalOr => false,
+            _ => return Err(tree.error("'&&' or '||'",
+                                       "bracketed conditions are written [<COND> && <COND>] \
+                                        or [<COND> || <COND>]")),
+        };
+        tree.next();
+        let rhs: Cond = parse_cond(tree)?;
+        tree.expect(Token::RightSquare, "missing ']' to close the condition")?;
+
+        if is_and {
+            Ok(Cond::And(Box::new(lhs), Box::new(rhs)))
         } else {
-            panic!("parse_cond: expected '+' or '-'");
+            Ok(Cond::Or(Box::new(lhs), Box::new(rhs)))
         }
     } else if tree.get_token().eq(&Token::Exclamation) {
-        tree.current_statement.push_str("!");
-        result = !parse_comp(&mut tree);
+        tree.next();
+        Ok(Cond::Not(Box::new(parse_cond(tree)?)))
     } else {
-        result = parse_comp(&mut tree);
+        Ok(Cond::Comp(parse_comp(tree)?))
     }
-
-    result
 }
 
-fn parse_comp(mut tree: &mut ParseTree) -> bool {
+fn parse_comp(tree: &mut ParseTree) -> Result<Comp, CoreError> {
 
     // (<OP> <COMP OP> <OP>)
 
-    let mut result: bool = false;
-    let mut op_a: i32;
-    let mut op_b: i32;
-    let mut cmp: u32;
-
-    if tree.get_token().eq(&Token::LeftParen) {
-        tree.current_statement.push('(');
-        tree.next();
-        op_a = parse_op(&mut tree);
-        cmp = parse_comp_op(&mut tree);
-        op_b = parse_op(&mut tree);
-        if tree.get_token().eq(&Token::RightParen) {
-            tree.current_statement.push(')');
-            tree.next();
-        }
-    } else {
-        panic!("parse_comp: expected '('");
-    }
-
-    match cmp {
-        0 => result = op_a == op_b,
-        1 => result = op_a != op_b,
-        2 => result = op_a <  op_b,
-        3 => result = op_a <= op_b,
-        4 => result = op_a >  op_b,
-        5 => result = op_a >= op_b,
-        _ => panic!("parse_comp: invalid comp_op")
-    }
+    tree.expect(Token::LeftParen, "comparisons are wrapped in parentheses, e.g. (X < 10)")?;
+    let lhs: Op = parse_op(tree)?;
+    let comp_op: CompOp = parse_comp_op(tree)?;
+    let rhs: Op = parse_op(tree)?;
+    tree.expect(Token::RightParen, "missing ')' to close the comparison")?;
 
-    result
+    Ok(Comp { lhs, comp_op, rhs })
 }
 
-fn parse_exp(mut tree: &mut ParseTree) -> i32 {
+fn parse_exp(tree: &mut ParseTree) -> Result<Exp, CoreError> {
 
     // <TRM>
     // <TRM> + <EXP>
     // <TRM> - <EXP>
 
-    let mut result: i32 = 0;
+    // Each term found joins the expression so far, so `10 - 3 - 2` is `(10 - 3) - 2`.
+    let mut exp: Exp = Exp::Trm(parse_trm(tree)?);
 
-    result = parse_trm(&mut tree);
-
-    if tree.get_token().eq(&Token::Addition) {
-        // handle addition, garble
-        tree.current_statement.push_str(" + ");
-        tree.next();
-        result += parse_exp(&mut tree);
-    } else if tree.get_token().eq(&Token::Subtraction) {
-        // handle subtraction
-        tree.current_statement.push_str(" - ");
-        tree.next();
-        result -= parse_exp(&mut tree);
+    loop {
+        let span: Span = tree.span();
+        if tree.get_token().eq(&Token::Addition) {
+            tree.next();
+            exp = Exp::Add(Box::new(exp), span, parse_trm(tree)?);
+        } else if tree.get_token().eq(&Token::Subtraction) {
+            tree.next();
+            exp = Exp::Sub(Box::new(exp), span, parse_trm(tree)?);
+        } else {
+            return Ok(exp);
+        }
     }
-
-    result
 }
 
-fn parse_trm(mut tree: &mut ParseTree) -> i32 {
+fn parse_trm(tree: &mut ParseTree) -> Result<Trm, CoreError> {
 
     // <OP>
     // <OP> * <TRM>
 
-    let mut result: i32 = parse_op(&mut tree);
+    let mut trm: Trm = Trm::Op(parse_op(tree)?);
 
-    if tree.get_token().eq(&Token::Multiplication) {
-        // handle multiplication, garble
-        tree.current_statement.push_str(" * ");
+    while tree.get_token().eq(&Token::Multiplication) {
+        let span: Span = tree.span();
         tree.next();
-        result *= parse_trm(&mut tree);
+        trm = Trm::Mul(Box::new(trm), span, parse_op(tree)?);
     }
 
-    result
+    Ok(trm)
 }
 
-fn parse_op(mut tree: &mut ParseTree) -> i32 {
+fn parse_op(tree: &mut ParseTree) -> Result<Op, CoreError> {
 
     // <NO>
     // <ID>
     // (<EXP>)
 
-    let mut result:i32 = 0;
+    match *tree.get_token() {
+        Token::LeftParen => {
+            tree.next(); // left paren
+            let exp: Exp = parse_exp(tree)?;
+            tree.expect(Token::RightParen, "missing ')' to close the expression")?;
+            Ok(Op::Exp(Box::new(exp)))
+        },
+        Token::Identifier(_) => Ok(Op::Id(parse_id(tree)?)),
+        Token::Integer(_) => {
+            let span: Span = tree.span();
+            Ok(Op::Int(parse_int(tree)?, span))
+        },
+        _ => Err(tree.error("integer, identifier or '('",
+                            "operands are integers, variables or parenthesized expressions")),
+    }
+}
 
-    let mut id_flag: bool = false;
-    let mut int_flag: bool = false;
+fn parse_comp_op(tree: &mut ParseTree) -> Result<CompOp, CoreError> {
+    let comp_op: CompOp = match *tree.get_token() {
+        Token::LogicalEquality => CompOp::Equal,
+        Token::LogicalInequality => CompOp::NotEqual,
+        Token::LessThan => CompOp::LessThan,
+        Token::LessThanEqual => CompOp::LessThanEqual,
+        Token::GreaterThan => CompOp::GreaterThan,
+        Token::GreaterThanEqual => CompOp::GreaterThanEqual,
+        _ => return Err(tree.error("comparison operator",
+                                   "comparisons use one of ==, !=, <, <=, > or >=")),
+    };
 
-    if tree.get_token().eq(&Token::LeftParen) {
-        tree.current_statement.push('(');
-        tree.next(); // left paren
-        result = parse_exp(&mut tree);
-        if tree.get_token().eq(&Token::RightParen) {
-            tree.current_statement.push(')');
-            tree.next();
-        } else {
-            panic!("parse_op: missing ')'");
-        }
-    } else {
-        match tree.get_token() {
-            &Token::Identifier(ref id) => id_flag = true,
-            &Token::Integer(ref i) => int_flag = true,
-            _ => panic!("parse_op: token is not identifier")
-        }
+    tree.next();
 
-        if id_flag {
-            id_flag = false;
-            let current_id: String = tree.retrieve_identifier().clone();
-            result = tree.retrieve_variable(&current_id);
-            parse_id(&mut tree);
-        } else if int_flag {
-            int_flag = false;
-            result = *tree.retrieve_integer();
-            parse_int(&mut tree);
-        } else {
-            panic!("parse_op: invalid argument")
+    Ok(comp_op)
+}
+
+fn parse_id(tree: &mut ParseTree) -> Result<Id, CoreError> {
+    let name: String = tree.retrieve_identifier()?;
+    let span: Span = tree.span();
+    tree.next();
+    Ok(Id { name, span })
+}
+
+fn parse_int(tree: &mut ParseTree) -> Result<Value, CoreError> {
+    let integer: Value = tree.retrieve_integer()?;
+    tree.next();
+    Ok(integer)
+}
+
+/*
+ * PRETTY-PRINTER
+ *
+ * The functions below reconstruct the canonical source text of a parsed program, one
+ * statement per line, indenting nested statement sequences by four spaces per level. The
+ * program's tokens come out in the same order they were parsed in.
+ */
+
+/// A line of canonical source, how many levels it is indented, and how many tokens it holds.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Line {
+    pub depth: usize,
+    pub text: String,
+    pub tokens: usize,
+}
+
+impl Line {
+    /// Renders the line, indenting `width` spaces per level.
+    pub fn indent(&self, width: usize) -> String {
+        format!("{}{}", " ".repeat(self.depth * width), self.text)
+    }
+}
+
+/// Canonical source under construction, written a token at a time.
+#[derive(Default)]
+struct Text {
+    text: String,
+    tokens: usize,
+}
+
+impl Text {
+    /// Appends `token`, separated from the one before it by a space unless it hugs a bracket,
+    /// '!', ',' or ';'.
+    fn push(&mut self, token: &str) {
+        let hugs_previous: bool = matches!(token, ";" | "," | ")" | "]");
+        let hugs_next: bool = self.text.ends_with(['(', '[', '!']);
+        if !self.text.is_empty() && !hugs_previous && !hugs_next {
+            self.text.push(' ');
         }
+
+        self.text.push_str(token);
+        self.tokens += 1;
     }
+}
 
-    result
+/// Renders a `Program` back into canonical Core source.
+pub fn print_prog(program: &Program) -> String {
+    print_lines(program).iter()
+        .map(|line| line.indent(4) + "\n")
+        .collect()
 }
 
-fn parse_comp_op(mut tree: &mut ParseTree) -> u32 {
+/// Lays out a `Program` as lines of canonical Core source. Each line holds the next run of the
+/// program's tokens, in the order they appear in its source.
+pub fn print_lines(program: &Program) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+
+    push_line(&mut lines, 0, keyword("program"));
+    for decl in &program.decl_seq {
+        let mut text: Text = keyword("int");
+        print_id_list(&mut text, &decl.id_list);
+        text.push(";");
+        push_line(&mut lines, 1, text);
+    }
+    push_line(&mut lines, 0, keyword("begin"));
+    print_stmt_seq(&mut lines, 1, &program.stmt_seq);
+    push_line(&mut lines, 0, keyword("end"));
+
+    lines
+}
+
+fn push_line(lines: &mut Vec<Line>, depth: usize, text: Text) {
+    lines.push(Line { depth, text: text.text, tokens: text.tokens });
+}
 
-    // This is like a literal "opcode" we will be using
-    // to perform the actual comparisons later on
-    let mut result: u32 = 0;
+/// A line that starts with `token`.
+fn keyword(token: &str) -> Text {
+    let mut text: Text = Text::default();
+    text.push(token);
+    text
+}
+
+fn print_id_list(text: &mut Text, id_list: &[Id]) {
+    for (index, id) in id_list.iter().enumerate() {
+        if index > 0 {
+            text.push(",");
+        }
+        text.push(&id.name);
+    }
+}
+
+/// The first line of a statement's canonical source, e.g. `while (X > 0) loop`.
+pub fn print_stmt_head(stmt: &Stmt) -> String {
+    let mut lines: Vec<Line> = Vec::new();
+    print_stmt(&mut lines, 0, stmt);
+
+    lines.swap_remove(0).text
+}
 
-    match tree.get_token() {
-        &Token::LogicalEquality => {
-            tree.current_statement.push_str(" == ");
-            result = 0;
+fn print_stmt_seq(lines: &mut Vec<Line>, depth: usize, stmt_seq: &[Stmt]) {
+    for stmt in stmt_seq {
+        print_stmt(lines, depth, stmt);
+    }
+}
+
+fn print_stmt(lines: &mut Vec<Line>, depth: usize, stmt: &Stmt) {
+    match stmt.kind {
+        StmtKind::Assign(ref id, ref exp) => {
+            let mut text: Text = keyword(&id.name);
+            text.push("=");
+            write_exp(&mut text, exp);
+            text.push(";");
+            push_line(lines, depth, text);
+        },
+        StmtKind::If(ref cond, ref then_seq, ref else_seq) => {
+            let mut text: Text = keyword("if");
+            write_cond(&mut text, cond);
+            text.push("then");
+            push_line(lines, depth, text);
+            print_stmt_seq(lines, depth + 1, then_seq);
+            if let Some(ref else_seq) = *else_seq {
+                push_line(lines, depth, keyword("else"));
+                print_stmt_seq(lines, depth + 1, else_seq);
+            }
+            push_line(lines, depth, end_keyword());
         },
-        &Token::LogicalInequality => {
-            tree.current_statement.push_str(" != ");
-            result = 1;
+        StmtKind::Loop(ref cond, ref stmt_seq) => {
+            let mut text: Text = keyword("while");
+            write_cond(&mut text, cond);
+            text.push("loop");
+            push_line(lines, depth, text);
+            print_stmt_seq(lines, depth + 1, stmt_seq);
+            push_line(lines, depth, end_keyword());
         },
-        &Token::LessThan => {
-            tree.current_statement.push_str(" < ");
-            result = 2;
+        StmtKind::In(ref id_list) => {
+            let mut text: Text = keyword("read");
+            print_id_list(&mut text, id_list);
+            text.push(";");
+            push_line(lines, depth, text);
         },
-        &Token::LessThanEqual => {
-            tree.current_statement.push_str(" <= ");
-            result = 3;
+        StmtKind::Out(ref id_list) => {
+            let mut text: Text = keyword("write");
+            print_id_list(&mut text, id_list);
+            text.push(";");
+            push_line(lines, depth, text);
         },
-        &Token::GreaterThan => {
-            tree.current_statement.push_str(" > ");
-            result = 4;
+    }
+}
+
+/// The `end;` closing an if or a loop.
+fn end_keyword() -> Text {
+    let mut text: Text = keyword("end");
+    text.push(";");
+    text
+}
+
+fn write_cond(text: &mut Text, cond: &Cond) {
+    match *cond {
+        Cond::Comp(ref comp) => {
+            text.push("(");
+            write_op(text, &comp.lhs);
+            text.push(print_comp_op(comp.comp_op));
+            write_op(text, &comp.rhs);
+            text.push(")");
         },
-        &Token::GreaterThanEqual => {
-            tree.current_statement.push_str(" >= ");
-            result = 5;
+        Cond::Not(ref cond) => {
+            text.push("!");
+            write_cond(text, cond);
         },
-        _ => panic!("parse_comp_op: unexpected comp op")
+        Cond::And(ref lhs, ref rhs) => write_junction(text, lhs, "&&", rhs),
+        Cond::Or(ref lhs, ref rhs) => write_junction(text, lhs, "||", rhs),
     }
+}
 
-    tree.next();
+/// Writes `[<lhs> <operator> <rhs>]`.
+fn write_junction(text: &mut Text, lhs: &Cond, operator: &str, rhs: &Cond) {
+    text.push("[");
+    write_cond(text, lhs);
+    text.push(operator);
+    write_cond(text, rhs);
+    text.push("]");
+}
+
+fn print_comp_op(comp_op: CompOp) -> &'static str {
+    match comp_op {
+        CompOp::Equal => "==",
+        CompOp::NotEqual => "!=",
+        CompOp::LessThan => "<",
+        CompOp::LessThanEqual => "<=",
+        CompOp::GreaterThan => ">",
+        CompOp::GreaterThanEqual => ">=",
+    }
+}
 
-    result
+pub fn print_exp(exp: &Exp) -> String {
+    let mut text: Text = Text::default();
+    write_exp(&mut text, exp);
+    text.text
 }
 
-fn parse_id(mut tree: &mut ParseTree) {
-    let identifier: String = tree.retrieve_identifier();
-    tree.current_statement.push_str(&identifier);
-    tree.context.push(identifier.clone());
-    if !tree.memory.contains_key(&identifier.clone()) {
-        tree.insert_variable(identifier.clone(), 0);
+pub fn print_trm(trm: &Trm) -> String {
+    let mut text: Text = Text::default();
+    write_trm(&mut text, trm);
+    text.text
+}
+
+fn write_exp(text: &mut Text, exp: &Exp) {
+    match *exp {
+        Exp::Trm(ref trm) => write_trm(text, trm),
+        Exp::Add(ref lhs, _, ref trm) => {
+            write_exp(text, lhs);
+            text.push("+");
+            write_trm(text, trm);
+        },
+        Exp::Sub(ref lhs, _, ref trm) => {
+            write_exp(text, lhs);
+            text.push("-");
+            write_trm(text, trm);
+        },
     }
-    tree.next();
 }
 
-fn parse_int(mut tree: &mut ParseTree) {
-    let integer: i32 = *tree.retrieve_integer();
-    tree.current_statement.push_str(&integer.to_string());
-    tree.next();
+fn write_trm(text: &mut Text, trm: &Trm) {
+    match *trm {
+        Trm::Op(ref op) => write_op(text, op),
+        Trm::Mul(ref lhs, _, ref op) => {
+            write_trm(text, lhs);
+            text.push("*");
+            write_op(text, op);
+        },
+    }
+}
+
+fn write_op(text: &mut Text, op: &Op) {
+    match *op {
+        Op::Int(ref value, _) => text.push(&value.to_string()),
+        Op::Id(ref id) => text.push(&id.name),
+        Op::Exp(ref exp) => {
+            text.push("(");
+            write_exp(text, exp);
+            text.push(")");
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokenizer::{Token, Lexeme, Span};
+    use super::*;
+
+    fn id(name: &str) -> Token {
+        Token::Identifier(name.to_string())
+    }
+
+    fn int(value: i32) -> Token {
+        Token::Integer(Value::from(value))
+    }
+
+    /// Parses hand-written tokens, giving the nth token the position 1:n.
+    fn parse(tokens: Vec<Token>) -> Result<Program, Vec<CoreError>> {
+        let lexemes: Vec<Lexeme> = tokens.into_iter()
+            .enumerate()
+            .map(|(i, token)| {
+                Lexeme::new(token, Span { offset: i, line: 1, column: i + 1, length: 1 })
+            })
+            .collect();
+        init_parser(lexemes)
+    }
+
+    #[test]
+    fn builds_ast_for_minimal_program() {
+        // program int X; begin X = 1 + X * 2; write X; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, int(1), Token::Addition,
+            id("X"), Token::Multiplication, int(2), Token::Semicolon,
+            Token::Write, id("X"), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let program: Program = parse(tokens).unwrap();
+
+        let at = |n: usize| Span { offset: n, line: 1, column: n + 1, length: 1 };
+        let x = |n: usize| Id { name: "X".to_string(), span: at(n) };
+
+        assert_eq!(program.decl_seq, vec![Decl { id_list: vec![x(2)] }]);
+        assert_eq!(program.stmt_seq, vec![
+            Stmt {
+                kind: StmtKind::Assign(x(5), Exp::Add(
+                    Box::new(Exp::Trm(Trm::Op(Op::Int(Value::from(1), at(7))))), at(8),
+                    Trm::Mul(Box::new(Trm::Op(Op::Id(x(9)))), at(10),
+                             Op::Int(Value::from(2), at(11))))),
+                span: Span { offset: 5, line: 1, column: 6, length: 1 },
+            },
+            Stmt {
+                kind: StmtKind::Out(vec![x(14)]),
+                span: Span { offset: 13, line: 1, column: 14, length: 1 },
+            },
+        ]);
+    }
+
+    #[test]
+    fn pretty_prints_nested_statements() {
+        // program int X, Y; begin if [(X < 1) || !(Y == 2)] then while (X > 0) loop
+        // X = X - 1; end; else read X; end; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Comma, id("Y"), Token::Semicolon,
+            Token::Begin,
+            Token::If, Token::LeftSquare,
+            Token::LeftParen, id("X"), Token::LessThan, int(1), Token::RightParen,
+            Token::LogicalOr, Token::Exclamation,
+            Token::LeftParen, id("Y"), Token::LogicalEquality, int(2), Token::RightParen,
+            Token::RightSquare, Token::Then,
+            Token::While, Token::LeftParen, id("X"), Token::GreaterThan, int(0),
+            Token::RightParen, Token::Loop,
+            id("X"), Token::Assignment, id("X"), Token::Subtraction, int(1),
+            Token::Semicolon,
+            Token::End, Token::Semicolon,
+            Token::Else, Token::Read, id("X"), Token::Semicolon, Token::End, Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let expected: &str = "program\n\
+                              \x20   int X, Y;\n\
+                              begin\n\
+                              \x20   if [(X < 1) || !(Y == 2)] then\n\
+                              \x20       while (X > 0) loop\n\
+                              \x20           X = X - 1;\n\
+                              \x20       end;\n\
+                              \x20   else\n\
+                              \x20       read X;\n\
+                              \x20   end;\n\
+                              end\n";
+
+        assert_eq!(print_prog(&parse(tokens).unwrap()), expected);
+    }
+
+    #[test]
+    fn reports_position_of_unexpected_token() {
+        // program int X; begin X = 1 write X; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, int(1),
+            Token::Write, id("X"), Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let errors: Vec<CoreError> = parse(tokens).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message(), "expected ';', found 'write'");
+        assert_eq!(errors[0].position(), Some(Position { line: 1, column: 9 }));
+        assert_eq!(errors[0].hint(), Some("missing ';' after assignment"));
+    }
+
+    #[test]
+    fn recovers_to_report_every_syntax_error() {
+        // program int X; begin X = 1 write X; if (X < ) then X = 2; end; read X end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            id("X"), Token::Assignment, int(1),
+            Token::Write, id("X"), Token::Semicolon,
+            Token::If, Token::LeftParen, id("X"), Token::LessThan, Token::RightParen, Token::Then,
+            id("X"), Token::Assignment, int(2), Token::Semicolon,
+            Token::End, Token::Semicolon,
+            Token::Read, id("X"),
+            Token::End, Token::EOF,
+        ];
+
+        let errors: Vec<(String, usize)> = parse(tokens).unwrap_err()
+            .iter()
+            .map(|error| (error.message().to_string(), error.position().unwrap().column))
+            .collect();
+
+        assert_eq!(errors, vec![
+            ("expected ';', found 'write'".to_string(), 9),
+            ("expected integer, identifier or '(', found ')'".to_string(), 16),
+            ("expected ';', found 'end'".to_string(), 26),
+        ]);
+    }
+
+    #[test]
+    fn rejects_empty_statement_sequences() {
+        // program int X; begin if (X < 1) then end; while (X < 1) loop end; end
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin,
+            Token::If, Token::LeftParen, id("X"), Token::LessThan, int(1), Token::RightParen,
+            Token::Then, Token::End, Token::Semicolon,
+            Token::While, Token::LeftParen, id("X"), Token::LessThan, int(1), Token::RightParen,
+            Token::Loop, Token::End, Token::Semicolon,
+            Token::End, Token::EOF,
+        ];
+
+        let columns: Vec<usize> = parse(tokens).unwrap_err()
+            .iter()
+            .map(|error| error.position().unwrap().column)
+            .collect();
+        assert_eq!(columns, vec![13, 22]);
+
+        let empty: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon, Token::Begin, Token::End,
+            Token::EOF,
+        ];
+        let errors: Vec<CoreError> = parse(empty).unwrap_err();
+        assert_eq!(errors[0].message(), "expected statement, found 'end'");
+    }
+
+    #[test]
+    fn reports_the_end_of_the_file_once() {
+        let errors: Vec<CoreError> = parse(vec![Token::EOF]).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message(), "expected 'program', found end of file");
+
+        // program int X; begin X = ;
+        let tokens: Vec<Token> = vec![
+            Token::Program, Token::Int, id("X"), Token::Semicolon,
+            Token::Begin, id("X"), Token::Assignment, Token::Semicolon, Token::EOF,
+        ];
+        let messages: Vec<String> = parse(tokens).unwrap_err().iter()
+            .map(|error| error.message().to_string())
+            .collect();
+        assert_eq!(messages, vec!["expected integer, identifier or '(', found ';'",
+                                  "expected 'end', found end of file"]);
+    }
 }
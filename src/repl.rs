@@ -0,0 +1,180 @@
+//! The REPL runs declarations and statements as they are typed, against a memory that lasts for
+//! the whole session.
+
+use std::io;
+use std::io::{BufRead, Write};
+use error::CoreError;
+use checker;
+use diagnostic;
+use executor;
+use executor::{Executor, Io, ConsoleIo, IteratorIo, Options};
+use parser;
+use parser::{Decl, Entry, Program};
+use tokenizer;
+use tokenizer::{Lexeme, Token};
+use value::Value;
+
+/// The name errors are reported against, in place of a file name.
+const SOURCE_NAME: &str = "<repl>";
+
+/// A REPL session: everything declared so far and the memory statements run against.
+pub struct Session<'a> {
+    executor: Executor<'a>,
+    decl_seq: Vec<Decl>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(io: &'a mut dyn Io, options: Options) -> Session<'a> {
+        Session { executor: Executor::new(io).with_options(options), decl_seq: Vec::new() }
+    }
+
+    /// Runs one complete entry: declarations add variables to the session, and statements run
+    /// straight away. Nothing is kept from an entry that fails to parse or check.
+    pub fn evaluate(&mut self, source: &[u8]) -> Result<(), Vec<CoreError>> {
+        let lexemes: Vec<Lexeme> = tokenizer::tokenize(source)?;
+
+        match parser::init_entry_parser(lexemes)? {
+            Entry::Decls(decl_seq) => {
+                let program: Program = Program {
+                    decl_seq: self.decl_seq.iter().chain(&decl_seq).cloned().collect(),
+                    stmt_seq: Vec::new(),
+                };
+                semantic_errors(&program)?;
+
+                for decl in &decl_seq {
+                    executor::execute_decl(&mut self.executor, decl);
+                }
+                self.decl_seq = program.decl_seq;
+                Ok(())
+            },
+            Entry::Stmts(stmt_seq) => {
+                let program: Program = Program { decl_seq: self.decl_seq.clone(), stmt_seq };
+                semantic_errors(&program)?;
+
+                executor::execute_stmt_seq(&mut self.executor, &program.stmt_seq)
+                    .map_err(|error| vec![error])
+            },
+        }
+    }
+}
+
+/// The checker's errors for `program`. Its warnings are left out, since a variable that is
+/// unused or unassigned so far may well be used or assigned by a later entry.
+fn semantic_errors(program: &Program) -> Result<(), Vec<CoreError>> {
+    let errors: Vec<CoreError> = checker::check_prog(program)
+        .into_iter()
+        .filter(|error| !error.is_warning())
+        .collect();
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Whether `source` holds a whole entry: every `if` and `while` has its `end`, and the last
+/// token is a ';'. Source with an illegal token counts as whole, so its errors are reported.
+pub fn is_complete(source: &[u8]) -> bool {
+    let lexemes: Vec<Lexeme> = match tokenizer::tokenize(source) {
+        Ok(lexemes) => lexemes,
+        Err(..) => return true,
+    };
+
+    let mut depth: i32 = 0;
+    for lexeme in &lexemes {
+        match lexeme.token {
+            Token::If | Token::While => depth += 1,
+            Token::End => depth -= 1,
+            _ => {},
+        }
+    }
+
+    // The last token before EOF.
+    let last: Option<&Token> = lexemes.iter().rev().nth(1).map(|lexeme| &lexeme.token);
+    depth <= 0 && last == Some(&Token::Semicolon)
+}
+
+/// Runs an interactive session on the terminal until end of input or `:quit`. When `input` is
+/// given, `read` statements take their values from it rather than prompting.
+pub fn init_repl(input: Option<Vec<Value>>, options: Options) -> Result<(), CoreError> {
+    let mut console: ConsoleIo = ConsoleIo;
+    let mut supplied: IteratorIo<_>;
+    let io: &mut dyn Io = match input {
+        Some(input) => {
+            supplied = IteratorIo::new(input);
+            &mut supplied
+        },
+        None => &mut console,
+    };
+
+    let mut session: Session = Session::new(io, options);
+    let stdin: io::Stdin = io::stdin();
+    let mut entry: String = String::new();
+
+    loop {
+        print!("{}", if entry.is_empty() { "core> " } else { "  ... " });
+        io::stdout().flush().map_err(|e| CoreError::Io(format!("stdout: {}", e)))?;
+
+        let mut line: String = String::new();
+        let read: usize = stdin.lock().read_line(&mut line)
+            .map_err(|e| CoreError::Io(format!("stdin: {}", e)))?;
+        if read == 0 {
+            println!();
+            return Ok(());
+        }
+
+        if entry.is_empty() {
+            match line.trim() {
+                ":quit" => return Ok(()),
+                "" => continue,
+                _ => {},
+            }
+        }
+
+        entry.push_str(&line);
+        if !is_complete(entry.as_bytes()) {
+            continue;
+        }
+
+        if let Err(errors) = session.evaluate(entry.as_bytes()) {
+            for error in &errors {
+                eprint!("{}", diagnostic::render(error, SOURCE_NAME, entry.as_bytes()));
+            }
+        }
+        entry.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use error::CoreError;
+    use executor::{BufferIo, Options};
+    use value::Value;
+    use super::{is_complete, Session};
+
+    #[test]
+    fn keeps_memory_between_entries() {
+        let mut io: BufferIo = BufferIo::new(vec![5]);
+        {
+            let mut session: Session = Session::new(&mut io, Options::default());
+            session.evaluate(b"int X, Y;").unwrap();
+            session.evaluate(b"read X;").unwrap();
+            session.evaluate(b"while (X > 0) loop Y = Y + X; X = X - 1; end;").unwrap();
+
+            let errors: Vec<CoreError> = session.evaluate(b"write Z;").unwrap_err();
+            assert_eq!(errors[0].message(), "Z is used but never declared");
+            let errors: Vec<CoreError> = session.evaluate(b"int Y;").unwrap_err();
+            assert_eq!(errors[0].message(), "Y is declared more than once");
+
+            session.evaluate(b"write Y;").unwrap();
+        }
+
+        assert_eq!(io.output, vec![Value::from(15)]);
+    }
+
+    #[test]
+    fn waits_for_the_end_of_each_block() {
+        assert!(is_complete(b"X = 1;"));
+        assert!(!is_complete(b"X = 1"));
+        assert!(!is_complete(b"while (X > 0) loop\n X = X - 1;\n"));
+        assert!(is_complete(b"while (X > 0) loop\n X = X - 1;\nend;"));
+        assert!(is_complete(b"X = 1 $"));
+    }
+}
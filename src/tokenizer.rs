@@ -6,13 +6,13 @@ use std::fs::File;
 use std::fmt;
 use std::io::prelude::*;
 use std::io::{BufReader};
-use std::process;
-use executor;
+use error::{CoreError, Diagnostic, Position};
+use value::Value;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     // For errors
-    Error,
+    Error(LexicalError),
 
     // Reserved words
     Whitespace,
@@ -42,7 +42,6 @@ pub enum Token {
     Addition,
     Subtraction,
     Multiplication,
-    Divison,
     LogicalInequality,
     LogicalEquality,
     LessThan,
@@ -54,175 +53,647 @@ pub enum Token {
     BlockComment,
 
     // User-defined
-    Integer(i32),
+    Integer(Value),
     Identifier(String),
 
     // Other
+    #[allow(clippy::upper_case_acronyms)]
     EOF,
 }
 
+impl Token {
+    /// Describes the token as it appears in Core source, for use in error messages.
+    pub fn describe(&self) -> String {
+        let text: &str = match *self {
+            Token::Error(..) => return "illegal token".to_string(),
+            Token::Whitespace => return "whitespace".to_string(),
+            Token::LineComment | Token::BlockComment => return "comment".to_string(),
+            Token::EOF => return "end of file".to_string(),
+            Token::Integer(ref value) => return format!("'{}'", value),
+            Token::Identifier(ref id) => return format!("'{}'", id),
+            Token::Program => "program",
+            Token::Begin => "begin",
+            Token::End => "end",
+            Token::Int => "int",
+            Token::If => "if",
+            Token::Then => "then",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::Loop => "loop",
+            Token::Read => "read",
+            Token::Write => "write",
+            Token::Semicolon => ";",
+            Token::Comma => ",",
+            Token::Assignment => "=",
+            Token::Exclamation => "!",
+            Token::LeftSquare => "[",
+            Token::RightSquare => "]",
+            Token::LogicalAnd => "&&",
+            Token::LogicalOr => "||",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::Addition => "+",
+            Token::Subtraction => "-",
+            Token::Multiplication => "*",
+            Token::LogicalInequality => "!=",
+            Token::LogicalEquality => "==",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::LessThanEqual => "<=",
+            Token::GreaterThanEqual => ">=",
+        };
+
+        format!("'{}'", text)
+    }
+
+    /// The token's number in the course's numbering: 1 to 30 for the reserved words and special
+    /// symbols, 31 for an integer, 32 for an identifier, 33 for the end of the file and 34 for an
+    /// illegal token. Whitespace, comments and '/' have no number.
+    pub fn code(&self) -> Option<u32> {
+        let code: u32 = match *self {
+            Token::Program => 1,
+            Token::Begin => 2,
+            Token::End => 3,
+            Token::Int => 4,
+            Token::If => 5,
+            Token::Then => 6,
+            Token::Else => 7,
+            Token::While => 8,
+            Token::Loop => 9,
+            Token::Read => 10,
+            Token::Write => 11,
+            Token::Semicolon => 12,
+            Token::Comma => 13,
+            Token::Assignment => 14,
+            Token::Exclamation => 15,
+            Token::LeftSquare => 16,
+            Token::RightSquare => 17,
+            Token::LogicalAnd => 18,
+            Token::LogicalOr => 19,
+            Token::LeftParen => 20,
+            Token::RightParen => 21,
+            Token::Addition => 22,
+            Token::Subtraction => 23,
+            Token::Multiplication => 24,
+            Token::LogicalInequality => 25,
+            Token::LogicalEquality => 26,
+            Token::LessThan => 27,
+            Token::GreaterThan => 28,
+            Token::LessThanEqual => 29,
+            Token::GreaterThanEqual => 30,
+            Token::Integer(..) => 31,
+            Token::Identifier(..) => 32,
+            Token::EOF => 33,
+            Token::Error(..) => 34,
+            Token::Whitespace | Token::LineComment | Token::BlockComment => {
+                return None;
+            },
+        };
+
+        Some(code)
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match *self {
+            Token::Integer(ref value) => write!(f, "Integer({})", value),
+            Token::Identifier(ref id) => write!(f, "Identifier({})", id),
+            Token::Error(..) => write!(f, "Error"),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
-fn exit_err () {
-    println!("Error: Illegal token encountered.");
-    process::exit(-1);
+/// How `core tokens` prints each token.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenFormat {
+    /// The course's numeric code, one per line.
+    Codes,
+    /// The token's name, one per line.
+    Names,
+    /// A JSON array of tokens along with their spans.
+    Json,
 }
 
-/// Verifies that the correct number (2) of arguments were passed.
-///
-/// # Examples
-///
-/// ```
-/// use tokenizer;
-///
-/// if tokenizer::is_valid_input(2) {
-///     // Interpreter was called correctly.
-/// } else {
-///     // Interpreter was not called correctly.
-/// }
-/// ```
-pub fn is_valid_input(arg_count: usize) -> bool {
-    /*
-     * Takes an unsigned integer as input, which corresponds to the number of arguments
-     * called with the Core interpreter executable. We are ensuring that the user called
-     * the interpreter with one and only one additional argument, the file name.
-     *
-     * TODO: May move test_is_valid_input here, changing this to doc comments.
-     */
-    if arg_count != 2 {
-        return false;
+/// The reason a stretch of source text is not a legal token.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LexicalError {
+    UnterminatedBlockComment,
+    UnterminatedLineComment,
+    SingleBar,
+    SingleAmpersand,
+    Division,
+    MinusAfterLetter,
+    LettersInInteger,
+    UnknownKeyword,
+    UppercaseInKeyword,
+    LowercaseInIdentifier,
+    LettersAfterDigits,
+    UnexpectedCharacter,
+}
+
+impl LexicalError {
+    pub fn reason(&self) -> &'static str {
+        match *self {
+            LexicalError::UnterminatedBlockComment => "unterminated block comment",
+            LexicalError::UnterminatedLineComment => {
+                "line comment is not terminated by a newline"
+            },
+            LexicalError::SingleBar => "a single '|'",
+            LexicalError::SingleAmpersand => "a single '&'",
+            LexicalError::Division => "Core has no division operator",
+            LexicalError::MinusAfterLetter => "'-' directly after a letter",
+            LexicalError::LettersInInteger => "letters in integer",
+            LexicalError::UnknownKeyword => "unknown keyword",
+            LexicalError::UppercaseInKeyword => "uppercase letters or digits in keyword",
+            LexicalError::LowercaseInIdentifier => "lowercase letters in identifier",
+            LexicalError::LettersAfterDigits => "letters after digits in identifier",
+            LexicalError::UnexpectedCharacter => "unexpected character",
+        }
     }
 
-    true
+    pub fn hint(&self) -> &'static str {
+        match *self {
+            LexicalError::UnterminatedBlockComment => "close the comment with '*/'",
+            LexicalError::UnterminatedLineComment => "end the file with a newline",
+            LexicalError::SingleBar => "logical or is written '||'",
+            LexicalError::SingleAmpersand => "logical and is written '&&'",
+            LexicalError::Division => "only '+', '-' and '*' are available",
+            LexicalError::MinusAfterLetter => "put a space before the '-'",
+            LexicalError::LettersInInteger => "separate the number from the name with a space",
+            LexicalError::UnknownKeyword | LexicalError::UppercaseInKeyword => {
+                "keywords are lowercase and identifiers are uppercase, e.g. int X;"
+            },
+            LexicalError::LowercaseInIdentifier => "identifiers are uppercase letters, e.g. XY",
+            LexicalError::LettersAfterDigits => "identifiers end with their digits, e.g. XY12",
+            LexicalError::UnexpectedCharacter => "remove it",
+        }
+    }
 }
 
-/// Informs the user of how to invoke the interpreter.
-///
-/// # Examples
-///
-/// use tokenizer;
-///
-/// if tokenizer::is_valid_input(4) {
-///
-/// }
-pub fn print_usage() {
-    /*
-     * This is a simple function that helps the user understand how to use our interpreter.
-     */
-    println!("Usage: ./core <core-source-file-name>");
+/// The stretch of source text a token was read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    /// Byte offset of the token's first character.
+    pub offset: usize,
+    /// 1-based line of the token's first character.
+    pub line: usize,
+    /// 1-based column of the token's first character.
+    pub column: usize,
+    /// Number of bytes the token covers.
+    pub length: usize,
+}
+
+impl Span {
+    pub fn position(&self) -> Position {
+        Position { line: self.line, column: self.column }
+    }
+}
+
+/// A token along with the span of source it was read from, and the comments and whitespace
+/// around it when the tokenizer was asked to keep them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lexeme {
+    pub token: Token,
+    pub span: Span,
+    /// Trivia between the end of the previous token's trailing trivia and this token.
+    pub leading: Vec<Trivia>,
+    /// Trivia after this token on the same line, up to and including the line break.
+    pub trailing: Vec<Trivia>,
+}
+
+impl Lexeme {
+    pub fn new(token: Token, span: Span) -> Lexeme {
+        Lexeme { token, span, leading: Vec::new(), trailing: Vec::new() }
+    }
+}
+
+/// Source text that carries no meaning for the parser.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TriviaKind {
+    /// A run of whitespace, ending at the first line break in it.
+    Whitespace,
+    LineComment,
+    BlockComment,
 }
 
-pub fn init_driver(file: &String) {
-    let output_vector: Vec<Token> = tokenize_file(file);
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
 
-    // parser::init_parser(output_vector.clone(), stdin_vector.clone());
-    executor::init_executor(output_vector.clone());
+impl Trivia {
+    pub fn is_comment(&self) -> bool {
+        self.kind != TriviaKind::Whitespace
+    }
 
-    // for token in output_vector {
-    //     match token {
-    //         Token::Error => exit_err(),
-    //         _ => println!("{}", token),
-    //     }
-    // }
+    /// The trivia's text within `source`, the text it was tokenized from.
+    pub fn text<'a>(&self, source: &'a [u8]) -> &'a [u8] {
+        &source[self.span.offset..self.span.offset + self.span.length]
+    }
 }
 
-fn tokenize_file(file: &String) -> Vec<Token> {
+/// Lists the tokens of Core source text in `format`, along with the errors for any illegal
+/// tokens listed. Like the course's tokenizer, codes and names stop at the first illegal token;
+/// JSON lists every token.
+pub fn list_tokens(source: &[u8], format: TokenFormat) -> (String, Vec<CoreError>) {
+    let mut lexemes: Vec<Lexeme> = tokenize_bytes(source, false, false);
+    if format != TokenFormat::Json {
+        if let Some(index) = lexemes.iter().position(|lexeme| lexeme.token.code() == Some(34)) {
+            lexemes.truncate(index + 1);
+        }
+    }
+
+    let listing: String = match format {
+        TokenFormat::Codes => lexemes.iter()
+            .filter_map(|lexeme| lexeme.token.code())
+            .map(|code| format!("{}\n", code))
+            .collect(),
+        TokenFormat::Names => lexemes.iter()
+            .map(|lexeme| format!("{}\n", lexeme.token))
+            .collect(),
+        TokenFormat::Json => tokens_json(source, &lexemes),
+    };
+
+    (listing, lexical_errors(source, &lexemes))
+}
+
+/// Renders lexemes as a JSON array, one token per line.
+fn tokens_json(source: &[u8], lexemes: &[Lexeme]) -> String {
+    let mut output: String = String::from("[\n");
+
+    for (index, lexeme) in lexemes.iter().enumerate() {
+        let span: Span = lexeme.span;
+        let text = String::from_utf8_lossy(&source[span.offset..span.offset + span.length]);
+        let code: String = match lexeme.token.code() {
+            Some(code) => code.to_string(),
+            None => "null".to_string(),
+        };
+
+        output.push_str(&format!("  {{\"code\": {}, \"name\": {}, \"text\": {}, \"line\": {}, \
+                                  \"column\": {}, \"offset\": {}, \"length\": {}",
+                                 code, json_string(&lexeme.token.to_string()), json_string(&text),
+                                 span.line, span.column, span.offset, span.length));
+        if let Token::Error(error) = lexeme.token {
+            output.push_str(&format!(", \"error\": {}", json_string(error.reason())));
+        }
+        output.push_str(if index + 1 < lexemes.len() { "},\n" } else { "}\n" });
+    }
+
+    output.push_str("]\n");
+    output
+}
+
+/// Quotes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut output: String = String::from("\"");
+
+    for c in text.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+/// Reads the raw bytes of a Core source file.
+pub fn read_file(file: &str) -> Result<Vec<u8>, CoreError> {
     /*
-     * It is in this tokenize_file() function that we will do the heavy lifting of opening input
+     * It is in this read_file() function that we will do the heavy lifting of opening input
      * files and reading in characters. Those characters will be used to form the designated
      * tokens, which will then be processed later on.
      */
 
     // Initializing a BufReader based on the file provided as input.
-    let mut f = BufReader::new(File::open(file).expect("File failed to open."));
+    match File::open(file) {
+        Ok(f) => read_source(BufReader::new(f), file),
+        Err(e) => Err(CoreError::Io(format!("{}: {}", file, e))),
+    }
+}
 
-    // A buffer to store the characters we read in from the file (ASCII = 1 byte).
+/// Reads the raw bytes of Core source from any reader, naming it `name` in errors.
+pub fn read_source<R: Read>(mut reader: R, name: &str) -> Result<Vec<u8>, CoreError> {
+    // A buffer to store the characters we read in (ASCII = 1 byte).
     let mut buf = Vec::<u8>::new();
 
-    // Iterating through the characters of the file . . .
-    f.read_to_end(&mut buf).expect("read_to_end failed");
+    // Iterating through the characters of the source . . .
+    if let Err(e) = reader.read_to_end(&mut buf) {
+        return Err(CoreError::Io(format!("{}: {}", name, e)));
+    }
+
+    Ok(buf)
+}
+
+/// Tokenizes a Core source file, returning each token along with the span of source it covers.
+pub fn tokenize_file(file: &str) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    tokenize(&read_file(file).map_err(|error| vec![error])?)
+}
+
+/// Tokenizes all of the Core source a reader supplies, naming it `name` in errors.
+pub fn tokenize_reader<R: Read>(reader: R, name: &str) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    tokenize(&read_source(reader, name).map_err(|error| vec![error])?)
+}
+
+/// Tokenizes Core source held in a string.
+pub fn tokenize_str(source: &str) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    tokenize(source.as_bytes())
+}
+
+/// Tokenizes Core source text, reporting every illegal token in it.
+pub fn tokenize(buf: &[u8]) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    check_lexemes(buf, tokenize_bytes(buf, false, false))
+}
+
+/// Tokenizes Core source text like `tokenize`, but attaches each comment to a neighbouring
+/// lexeme as trivia. With `whitespace`, whitespace is kept as trivia too, so `reassemble`
+/// gives back the source exactly.
+pub fn tokenize_with_trivia(buf: &[u8], whitespace: bool)
+                            -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    check_lexemes(buf, tokenize_bytes(buf, true, whitespace))
+}
+
+/// Rebuilds source text from lexemes tokenized out of `source`, along with their trivia.
+pub fn reassemble(source: &[u8], lexemes: &[Lexeme]) -> Vec<u8> {
+    let mut output: Vec<u8> = Vec::with_capacity(source.len());
+    for lexeme in lexemes {
+        for trivia in &lexeme.leading {
+            output.extend_from_slice(trivia.text(source));
+        }
+        let end: usize = lexeme.span.offset + lexeme.span.length;
+        output.extend_from_slice(&source[lexeme.span.offset..end]);
+        for trivia in &lexeme.trailing {
+            output.extend_from_slice(trivia.text(source));
+        }
+    }
+
+    output
+}
+
+fn check_lexemes(buf: &[u8], lexemes: Vec<Lexeme>) -> Result<Vec<Lexeme>, Vec<CoreError>> {
+    let errors: Vec<CoreError> = lexical_errors(buf, &lexemes);
+
+    if errors.is_empty() { Ok(lexemes) } else { Err(errors) }
+}
+
+fn lexical_errors(buf: &[u8], lexemes: &[Lexeme]) -> Vec<CoreError> {
+    lexemes.iter()
+        .filter_map(|lexeme| match lexeme.token {
+            Token::Error(error) => Some(lexical_error(buf, error, lexeme.span)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reports the illegal token `span` covers in `buf`.
+fn lexical_error(buf: &[u8], error: LexicalError, span: Span) -> CoreError {
+    let diagnostic: Diagnostic = match error {
+        LexicalError::UnterminatedBlockComment | LexicalError::UnterminatedLineComment => {
+            // Comments are reported where they start, rather than across the rest of the file.
+            Diagnostic::new(error.reason(), Span { length: 2, ..span })
+        },
+        _ => {
+            let text = String::from_utf8_lossy(&buf[span.offset..span.offset + span.length]);
+            Diagnostic::new(&format!("illegal token '{}': {}", text, error.reason()), span)
+        },
+    };
+
+    CoreError::Lexical(diagnostic.with_hint(error.hint()))
+}
+
+/// Scans `buf` into tokens, the last of which is `Token::EOF`. Illegal text becomes a
+/// `Token::Error` and scanning carries on after it. Comments and whitespace are attached to the
+/// lexemes as trivia when asked for, and dropped otherwise.
+fn tokenize_bytes(buf: &[u8], comments: bool, whitespace: bool) -> Vec<Lexeme> {
 
     // Defining the file 'state' which allows us to keep track of the interpreter's progress.
-    let mut i: usize = 0;
-    let mut tokenizer_output: Vec<Token> = Vec::new();
+    let mut cursor: Cursor = Cursor::new(buf);
+    let mut tokenizer_output: Vec<Lexeme> = Vec::new();
 
-    while i < buf.len() {
+    // Trivia waiting for the next token, and whether trivia still trails the last token because
+    // no line has ended since it.
+    let mut leading: Vec<Trivia> = Vec::new();
+    let mut trailing: bool = false;
 
-        let next_token: Token;
+    while let Some(byte) = cursor.peek() {
 
-        match buf[i] as char {
+        cursor.begin_token();
+        let next_token: Token = match byte as char {
 
             // Whitespace characters as defined by Rust language
-            ' ' => next_token = Token::Whitespace,
-            '\n' => next_token = Token::Whitespace,
-            '\r' => next_token = Token::Whitespace,
-            '\t' => next_token = Token::Whitespace,
+            ' ' | '\n' | '\r' | '\t' => cursor.single(Token::Whitespace),
 
             // Special tokens for parsing statements
-            ';' => next_token = Token::Semicolon,
-            ',' => next_token = Token::Comma,
-            '[' => next_token = Token::LeftSquare,
-            ']' => next_token = Token::RightSquare,
-            '(' => next_token = Token::LeftParen,
-            ')' => next_token = Token::RightParen,
+            ';' => cursor.single(Token::Semicolon),
+            ',' => cursor.single(Token::Comma),
+            '[' => cursor.single(Token::LeftSquare),
+            ']' => cursor.single(Token::RightSquare),
+            '(' => cursor.single(Token::LeftParen),
+            ')' => cursor.single(Token::RightParen),
 
             // Mathematical operators
-            '+' => next_token = Token::Addition,
-            '-' => next_token = tokenize_subtraction(&buf, &mut i),
-            '/' => next_token = tokenize_division(&buf, &mut i),
-            '*' => next_token = Token::Multiplication,
+            '+' => cursor.single(Token::Addition),
+            '-' => tokenize_subtraction(&mut cursor),
+            '/' => tokenize_division(&mut cursor),
+            '*' => cursor.single(Token::Multiplication),
 
             // Logical operators
-            '=' => next_token = tokenize_equal(&buf, &mut i),
-            '!' => next_token = tokenize_inequal(&buf, &mut i),
-            '<' => next_token = tokenize_lt_lte(&buf, &mut i),
-            '>' => next_token = tokenize_gt_gte(&buf, &mut i),
-            '|' => next_token = tokenize_logical_or(&buf, &mut i),
-            '&' => next_token = tokenize_logical_and(&buf, &mut i),
+            '=' => tokenize_equal(&mut cursor),
+            '!' => tokenize_inequal(&mut cursor),
+            '<' => tokenize_lt_lte(&mut cursor),
+            '>' => tokenize_gt_gte(&mut cursor),
+            '|' => tokenize_logical_or(&mut cursor),
+            '&' => tokenize_logical_and(&mut cursor),
 
             // User-defined values
-            '0'...'9' => next_token = tokenize_integer(&buf, &mut i),
-            'a'...'z' => next_token = tokenize_keyword(&buf, &mut i),
-            'A'...'Z' => next_token = tokenize_identifier(&buf, &mut i),
-            _ => next_token = Token::Error,
+            '0'..='9' => tokenize_integer(&mut cursor),
+            'a'..='z' => tokenize_keyword(&mut cursor),
+            'A'..='Z' => tokenize_identifier(&mut cursor),
+            _ => tokenize_unexpected(&mut cursor),
+        };
+
+        let span: Span = cursor.token_span();
+        let kind: Option<TriviaKind> = match next_token {
+            Token::Whitespace => Some(TriviaKind::Whitespace),
+            Token::LineComment => Some(TriviaKind::LineComment),
+            Token::BlockComment => Some(TriviaKind::BlockComment),
+            _ => None,
+        };
+
+        match kind {
+            Some(kind) => {
+                let keep: bool = if kind == TriviaKind::Whitespace { whitespace } else { comments };
+                let ends_line: bool = cursor.token_text().contains(&b'\n');
+                if keep {
+                    let trivia: Trivia = Trivia { kind, span };
+                    match tokenizer_output.last_mut() {
+                        Some(lexeme) if trailing => push_trivia(&mut lexeme.trailing, trivia, buf),
+                        _ => push_trivia(&mut leading, trivia, buf),
+                    }
+                }
+                if ends_line {
+                    trailing = false;
+                }
+            },
+            None => {
+                tokenizer_output.push(Lexeme {
+                    token: next_token,
+                    span,
+                    leading: std::mem::take(&mut leading),
+                    trailing: Vec::new(),
+                });
+                trailing = true;
+            },
         }
 
-        match next_token {
-            Token::Whitespace => print!(""),
-            Token::LineComment => print!(""),
-            Token::BlockComment => print!(""),
-            Token::Divison => print!(""),
-            _ => {
-                if next_token == Token::Error {
-                    tokenizer_output.push(next_token);
-                    break;
-                } else {
-                    tokenizer_output.push(next_token);
-                }
-            }
+    }
+
+    cursor.begin_token();
+    tokenizer_output.push(Lexeme {
+        token: Token::EOF,
+        span: cursor.token_span(),
+        leading,
+        trailing: Vec::new(),
+    });
+
+    tokenizer_output
+}
+
+/// Adds `trivia` to the end of `list`, merging a character of whitespace into the run before it
+/// unless that run already ends a line.
+fn push_trivia(list: &mut Vec<Trivia>, trivia: Trivia, buf: &[u8]) {
+    if let Some(last) = list.last_mut() {
+        let end: usize = last.span.offset + last.span.length;
+        if trivia.kind == TriviaKind::Whitespace && last.kind == TriviaKind::Whitespace
+            && end == trivia.span.offset && buf[end - 1] != b'\n' {
+            last.span.length += trivia.span.length;
+            return;
         }
+    }
+
+    list.push(trivia);
+}
+
+/// Reads through the source a byte at a time, keeping track of where the current token began.
+/// Looking before the start or past the end of the source gives `None`, so the tokenizing
+/// functions never index the buffer themselves.
+struct Cursor<'a> {
+    buf: &'a [u8],
+
+    /// The offset, line and column of the next byte to be read.
+    offset: usize,
+    position: Position,
+
+    /// The offset, line and column of the first byte of the current token.
+    start: usize,
+    start_position: Position,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(buf: &'a [u8]) -> Cursor<'a> {
+        let position: Position = Position { line: 1, column: 1 };
+        Cursor { buf, offset: 0, position, start: 0, start_position: position }
+    }
 
-        i += 1;
+    /// The next byte to be read.
+    fn peek(&self) -> Option<u8> {
+        self.buf.get(self.offset).cloned()
+    }
 
+    /// The byte after the next one.
+    fn peek_second(&self) -> Option<u8> {
+        self.buf.get(self.offset + 1).cloned()
     }
 
-    tokenizer_output.push(Token::EOF);
+    /// The byte just before the current token.
+    fn before_token(&self) -> Option<u8> {
+        self.start.checked_sub(1).map(|index| self.buf[index])
+    }
 
-    // buf = s.into_bytes();
-    buf.clear();
-    tokenizer_output
+    /// Reads the next byte.
+    fn bump(&mut self) -> Option<u8> {
+        let byte: u8 = self.peek()?;
+        self.offset += 1;
+
+        if byte == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(byte)
+    }
+
+    /// Reads the next byte if it is `byte`.
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.bump();
+            return true;
+        }
+
+        false
+    }
+
+    /// Reads bytes for as long as they satisfy `predicate`.
+    fn eat_while<F: Fn(u8) -> bool>(&mut self, predicate: F) {
+        while self.peek().is_some_and(&predicate) {
+            self.bump();
+        }
+    }
+
+    /// Reads a one-byte token.
+    fn single(&mut self, token: Token) -> Token {
+        self.bump();
+        token
+    }
+
+    fn begin_token(&mut self) {
+        self.start = self.offset;
+        self.start_position = self.position;
+    }
+
+    /// The text read since the current token began.
+    fn token_text(&self) -> &'a [u8] {
+        &self.buf[self.start..self.offset]
+    }
+
+    fn token_span(&self) -> Span {
+        Span {
+            offset: self.start,
+            line: self.start_position.line,
+            column: self.start_position.column,
+            length: self.offset - self.start,
+        }
+    }
+}
+
+fn is_lowercase(byte: Option<u8>) -> bool {
+    byte.is_some_and(|byte| byte.is_ascii_lowercase())
 }
 
-fn tokenize_equal(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let i: usize = *state as usize;
+fn is_uppercase(byte: Option<u8>) -> bool {
+    byte.is_some_and(|byte| byte.is_ascii_uppercase())
+}
 
-    if buf[i + 1] as char == '=' {
+fn is_digit(byte: Option<u8>) -> bool {
+    byte.is_some_and(|byte| byte.is_ascii_digit())
+}
+
+fn tokenize_equal(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'=') {
         // We have come across an equality operator.
-        *state += 1;
         return Token::LogicalEquality;
     }
 
@@ -230,290 +701,393 @@ fn tokenize_equal(buf: &Vec<u8>, state: &mut usize) -> Token {
     Token::Assignment
 }
 
-fn tokenize_logical_or(buf: &Vec<u8>, state: &mut usize) -> Token {
+fn tokenize_logical_or(cursor: &mut Cursor) -> Token {
     // The OR can produce an error token if the proceeding state is not an | character.
-    let i: usize = *state as usize;
-    if buf[i + 1] as char == '|' {
-        *state += 1;
+    cursor.bump();
+
+    if cursor.eat(b'|') {
         return Token::LogicalOr;
     }
 
     // For any other scenario, we fail to validate OR token and return error.
-    Token::Error
+    Token::Error(LexicalError::SingleBar)
 }
 
-fn tokenize_logical_and(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let i: usize = *state as usize;
-    if buf[i + 1] as char == '&' {
-        *state += 1;
+fn tokenize_logical_and(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'&') {
         return Token::LogicalAnd;
     }
 
-    Token::Error
+    Token::Error(LexicalError::SingleAmpersand)
 }
 
-fn tokenize_inequal(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let i: usize = *state as usize;
-    if buf[i + 1] as char == '=' {
-        *state += 1;
+fn tokenize_inequal(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'=') {
         return Token::LogicalInequality;
     }
 
     Token::Exclamation
 }
 
-fn tokenize_lt_lte(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let i: usize = *state as usize;
-    if buf[i + 1] as char == '=' {
-        *state += 1;
+fn tokenize_lt_lte(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'=') {
         return Token::LessThanEqual;
     }
 
     Token::LessThan
 }
 
-fn tokenize_gt_gte(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let i: usize = *state as usize;
-    if buf[i + 1] as char == '=' {
-        *state += 1;
+fn tokenize_gt_gte(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'=') {
         return Token::GreaterThanEqual;
     }
 
     Token::GreaterThan
 }
 
+/// Reads a character that starts no token, along with the rest of its UTF-8 encoding.
+fn tokenize_unexpected(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+    cursor.eat_while(|byte| byte & 0xc0 == 0x80);
+
+    Token::Error(LexicalError::UnexpectedCharacter)
+}
+
 /*
  * VALIDATED TOKENS
  *
  * The tokens below require a little extra validation. Specifically, they require whitespace
- * or one of the proceeding tokens in order to be identified correctly.
+ * or one of the proceeding tokens in order to be identified correctly. When one is malformed,
+ * the rest of its word is read into the error so scanning resumes at the next token.
  */
 
-fn tokenize_subtraction(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let mut i: usize = *state as usize;
+fn word_error(cursor: &mut Cursor, error: LexicalError) -> Token {
+    cursor.eat_while(|byte| byte.is_ascii_alphanumeric());
+    Token::Error(error)
+}
+
+fn tokenize_subtraction(cursor: &mut Cursor) -> Token {
+    cursor.bump();
 
     // Validating separation between tokens
-    if (buf[i - 1] as char >= 'a' && buf[i - 1] as char <= 'z') ||
-       (buf[i - 1] as char >= 'A' && buf[i - 1] as char <= 'Z') {
-        return Token::Error;
+    let before: Option<u8> = cursor.before_token();
+    if is_lowercase(before) || is_uppercase(before) {
+        return Token::Error(LexicalError::MinusAfterLetter);
     }
 
-    let start_number = buf[i] as char;
-    let mut integer: String = start_number.to_string();
-
-    if !(buf[i + 1] as char <= '9' && buf[i + 1] as char >= '0') {
-        // println!("{} {} {}", buf[i] as char, buf[i + 1] as char, buf[i + 2] as char);
-        // println!("SUBTRACTION");
+    if !is_digit(cursor.peek()) {
         return Token::Subtraction;
-    } else {
-        i + 1;
-        while i + 1 < buf.len() {
-            i += 1;
-            if buf[i] as char >= '0' && buf[i] as char <= '9' {
-                let new_digit = buf[i] as char;
-                integer.push_str(&new_digit.to_string());
-            } else if (buf[i] as char >= 'a' && buf[i] as char <= 'z') ||
-                      (buf[i] as char >= 'A' && buf[i] as char <= 'z') {
-                return Token::Error;
-            } else {
-                i -= 1;
-                break;
+    }
+
+    // A minus sign directly before a digit makes a negative literal.
+    tokenize_digits(cursor)
+}
+
+fn tokenize_division(cursor: &mut Cursor) -> Token {
+    cursor.bump();
+
+    if cursor.eat(b'/') {
+        // The comment runs up to, but not including, the end of the line.
+        loop {
+            cursor.eat_while(|byte| byte != b'\r' && byte != b'\n');
+            match (cursor.peek(), cursor.peek_second()) {
+                (None, _) => return Token::Error(LexicalError::UnterminatedLineComment),
+                (Some(b'\n'), _) | (Some(b'\r'), Some(b'\n')) => return Token::LineComment,
+                _ => cursor.bump(),
+            };
+        }
+    }
+
+    if cursor.eat(b'*') {
+        // The comment runs through the closing "*/".
+        loop {
+            match cursor.bump() {
+                None => return Token::Error(LexicalError::UnterminatedBlockComment),
+                Some(b'*') if cursor.eat(b'/') => return Token::BlockComment,
+                Some(..) => {},
             }
         }
     }
 
-    // Update the state of our buffer.
-    *state = i;
+    // Core has no division, so a '/' that starts no comment is an error.
+    Token::Error(LexicalError::Division)
+}
 
-    // For a more detailed token
-    let integer_result = integer.parse().unwrap();
+fn tokenize_integer(cursor: &mut Cursor) -> Token {
+    // Words always run up to a character that is not a letter or digit, so nothing can come
+    // directly before this one.
+    tokenize_digits(cursor)
+}
 
-    // println!("TOKEN: {}", Token::Integer(integer_result));
-    Token::Integer(integer_result)
+/// Reads the digits of an integer literal, along with any minus sign already read.
+fn tokenize_digits(cursor: &mut Cursor) -> Token {
+    cursor.eat_while(|byte| byte.is_ascii_digit());
+    if is_lowercase(cursor.peek()) || is_uppercase(cursor.peek()) {
+        return word_error(cursor, LexicalError::LettersInInteger);
+    }
 
+    // For a more detailed token
+    let text = String::from_utf8_lossy(cursor.token_text());
+    Token::Integer(text.parse().expect("a run of digits is always an integer"))
 }
 
-fn tokenize_division(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let mut i: usize = *state as usize;
+fn tokenize_keyword(cursor: &mut Cursor) -> Token {
+    cursor.eat_while(|byte| byte.is_ascii_lowercase());
+    if is_uppercase(cursor.peek()) || is_digit(cursor.peek()) {
+        return word_error(cursor, LexicalError::UppercaseInKeyword);
+    }
 
-    if buf[i + 1] as char == '/' {
-        while buf[i] as char != '\n' {
-            i = i + 1;
-            // println!("Token: {}", buf[i] as char);
-        }
+    match cursor.token_text() {
+        b"program" => Token::Program,
+        b"begin" => Token::Begin,
+        b"end" => Token::End,
+        b"int" => Token::Int,
+        b"if" => Token::If,
+        b"then" => Token::Then,
+        b"else" => Token::Else,
+        b"while" => Token::While,
+        b"loop" => Token::Loop,
+        b"read" => Token::Read,
+        b"write" => Token::Write,
+        _ => Token::Error(LexicalError::UnknownKeyword),
+    }
+}
 
-        *state = i - 1;
-        // println!("State: {}", buf[*state as usize] as char);
-        return Token::LineComment;
-    } else if buf[i + 1] as char == '*' {
-        i += 2;
-        let mut found_block_end: bool = false;
-        while buf[i] as char != '*' && !found_block_end {
-            i = i+1;
-
-            if buf[i] as char == '*' {
-                if buf[i + 1] as char == '/' {
-                    i = i + 2;
-                    found_block_end = true;
-                } else {
-                    i += 1;
-                }
-            }
-        }
+fn tokenize_identifier(cursor: &mut Cursor) -> Token {
+    // Uppercase letters, then digits.
+    cursor.eat_while(|byte| byte.is_ascii_uppercase());
+    if is_lowercase(cursor.peek()) {
+        return word_error(cursor, LexicalError::LowercaseInIdentifier);
+    }
 
-        *state = i;
-        return Token::BlockComment;
-    } else {
-        return Token::Divison;
+    cursor.eat_while(|byte| byte.is_ascii_digit());
+    if is_lowercase(cursor.peek()) || is_uppercase(cursor.peek()) {
+        return word_error(cursor, LexicalError::LettersAfterDigits);
     }
+
+    Token::Identifier(String::from_utf8_lossy(cursor.token_text()).into_owned())
 }
 
-fn tokenize_integer(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let mut i: usize = *state as usize;
+#[cfg(test)]
+mod test {
 
-    // Validating separation between tokens
-    if (buf[i - 1] as char >= 'a' && buf[i - 1] as char <= 'z') ||
-       (buf[i - 1] as char >= 'A' && buf[i - 1] as char <= 'Z') {
-        return Token::Error;
-    }
-
-    let start_number = buf[i] as char;
-    let mut integer: String = start_number.to_string();
-
-    while i + 1 < buf.len() {
-        i += 1;
-        if buf[i] as char >= '0' && buf[i] as char <= '9' {
-            let new_digit = buf[i] as char;
-            integer.push_str(&new_digit.to_string());
-        } else if (buf[i] as char >= 'a' && buf[i] as char <= 'z') ||
-                  (buf[i] as char >= 'A' && buf[i] as char <= 'z') {
-            return Token::Error;
-        } else {
-            i -= 1;
-            break;
-        }
+    #[test]
+    fn records_span_of_each_token() {
+        use super::{Token, Span, Value};
+
+        let lexemes = super::tokenize_bytes(b"program\n  int XY1 <= -42;", false, false);
+        let spans: Vec<(Token, Span)> = lexemes.into_iter()
+            .map(|lexeme| (lexeme.token, lexeme.span))
+            .collect();
+
+        assert_eq!(spans, vec![
+            (Token::Program, Span { offset: 0, line: 1, column: 1, length: 7 }),
+            (Token::Int, Span { offset: 10, line: 2, column: 3, length: 3 }),
+            (Token::Identifier("XY1".to_string()), Span { offset: 14, line: 2, column: 7, length: 3 }),
+            (Token::LessThanEqual, Span { offset: 18, line: 2, column: 11, length: 2 }),
+            (Token::Integer(Value::from(-42)), Span { offset: 21, line: 2, column: 14, length: 3 }),
+            (Token::Semicolon, Span { offset: 24, line: 2, column: 17, length: 1 }),
+            (Token::EOF, Span { offset: 25, line: 2, column: 18, length: 0 }),
+        ]);
     }
 
-    // Update the state of our buffer.
-    *state = i;
+    #[test]
+    fn tokenizes_readers_and_strings_alike() {
+        use std::io;
+        use super::{Lexeme, CoreError};
+
+        let source: &str = "program int X; begin X = 1; write X; end";
+        let from_str: Vec<Lexeme> = super::tokenize_str(source).unwrap();
+        let from_reader: Vec<Lexeme> = super::tokenize_reader(source.as_bytes(), "-").unwrap();
+        assert_eq!(from_str, from_reader);
+
+        struct Broken;
+        impl io::Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("pipe closed"))
+            }
+        }
 
-    // For a more detailed token
-    let integer_result = integer.parse().unwrap();
+        let errors: Vec<CoreError> = super::tokenize_reader(Broken, "<stdin>").unwrap_err();
+        assert_eq!(errors, vec![CoreError::Io("<stdin>: pipe closed".to_string())]);
+    }
 
-    Token::Integer(integer_result)
-}
+    #[test]
+    fn attaches_trivia_to_neighbouring_tokens() {
+        use super::{TriviaKind, Lexeme};
+
+        let source: &[u8] = b"// header\r\nprogram int X; /* a\nb */ // x\n\n  begin end\t\n\
+                              // done\n";
+        let lexemes: Vec<Lexeme> = super::tokenize_with_trivia(source, true).unwrap();
+        assert_eq!(super::reassemble(source, &lexemes), source);
+
+        let kinds = |trivia: &[super::Trivia]| -> Vec<(TriviaKind, String)> {
+            trivia.iter()
+                .map(|trivia| (trivia.kind, String::from_utf8_lossy(trivia.text(source)).into()))
+                .collect()
+        };
+        assert_eq!(kinds(&lexemes[0].leading), vec![
+            (TriviaKind::LineComment, "// header".to_string()),
+            (TriviaKind::Whitespace, "\r\n".to_string()),
+        ]);
+        assert_eq!(kinds(&lexemes[3].trailing), vec![
+            (TriviaKind::Whitespace, " ".to_string()),
+            (TriviaKind::BlockComment, "/* a\nb */".to_string()),
+        ]);
+        assert_eq!(kinds(&lexemes[4].leading), vec![
+            (TriviaKind::Whitespace, " ".to_string()),
+            (TriviaKind::LineComment, "// x".to_string()),
+            (TriviaKind::Whitespace, "\n".to_string()),
+            (TriviaKind::Whitespace, "\n".to_string()),
+            (TriviaKind::Whitespace, "  ".to_string()),
+        ]);
+
+        let comments: Vec<Lexeme> = super::tokenize_with_trivia(source, false).unwrap();
+        assert!(comments.iter().all(|lexeme| {
+            lexeme.leading.iter().chain(&lexeme.trailing).all(|trivia| trivia.is_comment())
+        }));
+        assert_eq!(comments.last().unwrap().leading.len(), 1);
+    }
 
-fn tokenize_keyword(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let mut i: usize = *state as usize;
+    #[test]
+    fn reports_unterminated_comments_where_they_start() {
+        use error::Position;
+
+        let errors: Vec<(String, Option<Position>)> = [
+            &b"program int X;\n  /* never closed\n begin end\n"[..],
+            &b"program int X; begin end // no newline"[..],
+            &b"program int X; begin end /*/"[..],
+        ].iter()
+            .flat_map(|source| super::tokenize(source).unwrap_err())
+            .map(|error| (error.message().to_string(), error.position()))
+            .collect();
+
+        assert_eq!(errors, vec![
+            ("unterminated block comment".to_string(), Some(Position { line: 2, column: 3 })),
+            ("line comment is not terminated by a newline".to_string(),
+             Some(Position { line: 1, column: 26 })),
+            ("unterminated block comment".to_string(), Some(Position { line: 1, column: 26 })),
+        ]);
+    }
 
-    // Adding a special check because our programs will begin with a keyword.
-    if i > 0 {
-        // Validating separation between tokens.
-        if (buf[i - 1] as char >= 'A' && buf[i - 1] as char <= 'Z') ||
-           (buf[i - 1] as char >= '0' && buf[i - 1] as char <= '9') {
-            return Token::Error;
-        }
+    #[test]
+    fn reports_every_illegal_token_and_keeps_going() {
+        use error::Position;
+        use super::{Token, Lexeme};
+
+        let source: &[u8] = b"program int Xab, Y; begin\n  Y = 12q / 2 | Y;\n  \
+                              write Y; end \xc3\xa9";
+        let summary: Vec<(String, Option<Position>)> = super::tokenize(source).unwrap_err()
+            .iter()
+            .map(|error| (error.message().to_string(), error.position()))
+            .collect();
+
+        assert_eq!(summary, vec![
+            ("illegal token 'Xab': lowercase letters in identifier".to_string(),
+             Some(Position { line: 1, column: 13 })),
+            ("illegal token '12q': letters in integer".to_string(),
+             Some(Position { line: 2, column: 7 })),
+            ("illegal token '/': Core has no division operator".to_string(),
+             Some(Position { line: 2, column: 11 })),
+            ("illegal token '|': a single '|'".to_string(), Some(Position { line: 2, column: 15 })),
+            ("illegal token '\u{e9}': unexpected character".to_string(),
+             Some(Position { line: 3, column: 16 })),
+        ]);
+
+        // Everything between the errors is still tokenized.
+        let lexemes: Vec<Lexeme> = super::tokenize_bytes(source, false, false);
+        assert_eq!(lexemes.len(), 21);
+        assert_eq!(lexemes[18].token, Token::End);
     }
 
-    let start_letter = buf[i] as char;
-    let mut keyword: String = start_letter.to_string();
+    #[test]
+    fn numbers_tokens_as_the_course_does() {
+        let source: &[u8] = b"program int X; begin X===328;XY74||\n\"Z\"";
+        let lexemes = super::tokenize_bytes(source, false, false);
 
-    while i + 1 < buf.len() {
-        i += 1;
-        if buf[i] as char >= 'a' && buf[i] as char <= 'z' {
-            let new_char = buf[i] as char;
-            keyword.push_str(&new_char.to_string());
-        } else if (buf[i] as char >= 'A' && buf[i] as char <= 'Z') ||
-                  (buf[i] as char >= '0' && buf[i] as char <= '9') {
-            return Token::Error;
-        } else {
-            i -= 1;
-            break;
-        }
+        let codes: Vec<u32> = lexemes.iter().filter_map(|lexeme| lexeme.token.code()).collect();
+        assert_eq!(codes, vec![1, 4, 32, 12, 2, 32, 26, 14, 31, 12, 32, 19, 34, 32, 34, 33]);
+
+        let json: String = super::tokens_json(source, &lexemes[12..13]);
+        assert_eq!(json, "[\n  {\"code\": 34, \"name\": \"Error\", \"text\": \"\\\"\", \
+                          \"line\": 2, \"column\": 1, \"offset\": 36, \"length\": 1, \
+                          \"error\": \"unexpected character\"}\n]\n");
     }
 
-    // Update the state of our buffer.
-    *state = i;
-
-    match keyword.as_ref() {
-        "program" => return Token::Program,
-        "begin" => return Token::Begin,
-        "end" => return Token::End,
-        "int" => return Token::Int,
-        "if" => return Token::If,
-        "then" => return Token::Then,
-        "else" => return Token::Else,
-        "while" => return Token::While,
-        "loop" => return Token::Loop,
-        "read" => return Token::Read,
-        "write" => return Token::Write,
-        _ => return Token::Error,
-    }
-}
-
-fn tokenize_identifier(buf: &Vec<u8>, state: &mut usize) -> Token {
-    let mut i: usize = *state as usize;
-    // Validating separation between tokens.
-    if (buf[i - 1] as char >= 'a' && buf[i - 1] as char <= 'z') ||
-       (buf[i - 1] as char >= '0' && buf[i - 1] as char <= '9') {
-        return Token::Error;
-    }
-
-    let start_letter = buf[i] as char;
-    let mut identifier: String = start_letter.to_string();
-    let mut char_flag: bool = true;
-    let mut nmbr_flag: bool = true;
-
-    while (i + 1 < buf.len()) && char_flag {
-        i += 1;
-        if buf[i] as char >= 'A' && buf[i] as char <= 'Z' {
-            let new_char = buf[i] as char;
-            identifier.push_str(&new_char.to_string());
-        } else if buf[i] as char >= 'a' && buf[i] as char <= 'z' {
-            return Token::Error;
-        } else {
-            i -= 1;
-            char_flag = !char_flag;
+    /// Tokenizes `source` every way, checking the lexemes and trivia fall in order within it.
+    fn tokenize_anything(source: &[u8]) {
+        use super::Lexeme;
+
+        let _ = super::tokenize(source);
+        let _ = super::tokenize_with_trivia(source, false);
+
+        // Illegal tokens are kept too, so any source at all reassembles exactly.
+        let lexemes: Vec<Lexeme> = super::tokenize_bytes(source, true, true);
+        assert_eq!(super::reassemble(source, &lexemes), source);
+        let mut end: usize = 0;
+        for lexeme in &lexemes {
+            let spans = lexeme.leading.iter().map(|trivia| trivia.span)
+                .chain(Some(lexeme.span))
+                .chain(lexeme.trailing.iter().map(|trivia| trivia.span));
+            for span in spans {
+                assert!(span.offset >= end && span.offset + span.length <= source.len(),
+                        "{:?} is out of place in {:?}", span, source);
+                end = span.offset + span.length;
+            }
         }
     }
 
-    while (i + 1 < buf.len()) && nmbr_flag {
-        i += 1;
-        if buf[i] as char >= '0' && buf[i] as char <= '9' {
-            let new_digit = buf[i] as char;
-            identifier.push_str(&new_digit.to_string());
-        } else if (buf[i] as char >= 'a' && buf[i] as char <= 'z') ||
-                  (buf[i] as char >= 'A' && buf[i] as char <= 'z') {
-            return Token::Error;
-        } else {
-            i -= 1;
-            nmbr_flag = !nmbr_flag;
+    #[test]
+    fn never_panics_on_short_inputs() {
+        let alphabet: &[u8] = b" \n\r=!<>|&-/*;aZ0\xff";
+
+        for &a in alphabet {
+            for &b in alphabet {
+                for &c in alphabet {
+                    tokenize_anything(&[a]);
+                    tokenize_anything(&[a, b]);
+                    tokenize_anything(&[a, b, c]);
+                }
+            }
         }
     }
 
-    *state = i;
-
-    Token::Identifier(identifier)
-}
-
-#[cfg(test)]
-mod test {
-
     #[test]
-    fn correctly_verifies_argument_count() {
-        let case_a: Vec<String> = vec!["Hello ".to_string(), "world!".to_string()];
-        let case_b: Vec<String> =
-            vec!["This ".to_string(), "won't ".to_string(), "work!".to_string()];
-        let case_c: Vec<String> = vec!["Goodbye!".to_string()];
-
-        assert_eq!(super::is_valid_input(case_a.len()),
-                   true,
-                   "Case A should be valid, but wasn't.");
-        assert_eq!(super::is_valid_input(case_b.len()),
-                   false,
-                   "Case B should be invalid, but wasn't.");
-        assert_eq!(super::is_valid_input(case_c.len()),
-                   false,
-                   "Case C should be invalid, but wasn't.");
+    fn never_panics_on_arbitrary_bytes() {
+        let fragments: [&[u8]; 12] = [
+            b"program", b"int", b"begin", b"end", b"X1", b"-42", b"==", b"//", b"/*", b"*/", b"\n",
+            b"xY",
+        ];
+
+        // A xorshift generator, so every run sees the same inputs.
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..5000 {
+            let length: usize = (next() % 24) as usize;
+            let mut source: Vec<u8> = Vec::new();
+            for _ in 0..length {
+                let choice: u64 = next();
+                if choice & 1 == 0 {
+                    source.push((choice >> 8) as u8);
+                } else {
+                    source.extend_from_slice(fragments[(choice >> 8) as usize % fragments.len()]);
+                }
+            }
+            tokenize_anything(&source);
+        }
     }
 }
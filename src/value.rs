@@ -0,0 +1,369 @@
+//! Integer values in a Core program. Values that fit in 32 bits are stored inline, and anything
+//! larger grows into an arbitrary-precision integer, so literals and results of any size can be
+//! represented.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// The base of each limb of a `BigInt`, chosen so decimal conversion is a matter of padding.
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+/// A Core integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value(Repr);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Repr {
+    Small(i32),
+
+    /// Only ever holds values outside the range of `i32`, so each value has one representation.
+    Big(BigInt),
+}
+
+impl Value {
+    /// The value as an `i32`, if it fits.
+    pub fn to_i32(&self) -> Option<i32> {
+        match self.0 {
+            Repr::Small(value) => Some(value),
+            Repr::Big(..) => None,
+        }
+    }
+
+    /// The low 32 bits of the value in two's complement.
+    pub fn wrapping_i32(&self) -> i32 {
+        match self.0 {
+            Repr::Small(value) => value,
+            Repr::Big(ref big) => big.wrapping_i32(),
+        }
+    }
+
+    /// The value clamped to the range of `i32`.
+    pub fn saturating_i32(&self) -> i32 {
+        match self.0 {
+            Repr::Small(value) => value,
+            Repr::Big(ref big) if big.negative => i32::MIN,
+            Repr::Big(..) => i32::MAX,
+        }
+    }
+
+    pub fn add(&self, other: &Value) -> Value {
+        match (&self.0, &other.0) {
+            (&Repr::Small(a), &Repr::Small(b)) => Value::from(a as i64 + b as i64),
+            _ => Value::from_big(self.to_big().add(&other.to_big())),
+        }
+    }
+
+    pub fn sub(&self, other: &Value) -> Value {
+        match (&self.0, &other.0) {
+            (&Repr::Small(a), &Repr::Small(b)) => Value::from(a as i64 - b as i64),
+            _ => Value::from_big(self.to_big().add(&other.to_big().neg())),
+        }
+    }
+
+    pub fn mul(&self, other: &Value) -> Value {
+        match (&self.0, &other.0) {
+            (&Repr::Small(a), &Repr::Small(b)) => Value::from(a as i64 * b as i64),
+            _ => Value::from_big(self.to_big().mul(&other.to_big())),
+        }
+    }
+
+    fn from_big(big: BigInt) -> Value {
+        match big.to_i64() {
+            Some(value) if value >= i32::MIN as i64 && value <= i32::MAX as i64 => {
+                Value(Repr::Small(value as i32))
+            },
+            _ => Value(Repr::Big(big)),
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self.0 {
+            Repr::Small(value) => BigInt::from_i64(value as i64),
+            Repr::Big(ref big) => big.clone(),
+        }
+    }
+}
+
+impl Default for Value {
+    fn default() -> Value {
+        Value(Repr::Small(0))
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value {
+        Value(Repr::Small(value))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        if value >= i32::MIN as i64 && value <= i32::MAX as i64 {
+            Value(Repr::Small(value as i32))
+        } else {
+            Value(Repr::Big(BigInt::from_i64(value)))
+        }
+    }
+}
+
+impl PartialEq<i32> for Value {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == Repr::Small(*other)
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (&self.0, &other.0) {
+            (&Repr::Small(a), &Repr::Small(b)) => a.cmp(&b),
+            _ => self.to_big().cmp(&other.to_big()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The reason a string could not be read as a `Value`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseValueError;
+
+impl fmt::Display for ParseValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not an integer")
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseValueError;
+
+    /// Reads an optionally negative run of decimal digits.
+    fn from_str(text: &str) -> Result<Value, ParseValueError> {
+        if let Ok(value) = text.parse::<i32>() {
+            return Ok(Value(Repr::Small(value)));
+        }
+
+        BigInt::parse(text).map(Value::from_big).ok_or(ParseValueError)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Repr::Small(value) => write!(f, "{}", value),
+            Repr::Big(ref big) => write!(f, "{}", big),
+        }
+    }
+}
+
+/// A sign and a magnitude stored as little-endian base 10^9 limbs. Zero has no limbs and is
+/// never negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_i64(value: i64) -> BigInt {
+        let mut magnitude: u64 = value.unsigned_abs();
+        let mut limbs: Vec<u32> = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+
+        BigInt { negative: value < 0, limbs }
+    }
+
+    fn parse(text: &str) -> Option<BigInt> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        // Taking nine digits at a time from the least significant end.
+        let mut limbs: Vec<u32> = Vec::new();
+        let mut end: usize = digits.len();
+        while end > 0 {
+            let start: usize = end.saturating_sub(BASE_DIGITS);
+            limbs.push(digits[start..end].parse::<u32>().ok()?);
+            end = start;
+        }
+
+        Some(BigInt::normalize(negative, limbs))
+    }
+
+    /// Drops leading zero limbs, and the sign of zero.
+    fn normalize(negative: bool, mut limbs: Vec<u32>) -> BigInt {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+
+        BigInt { negative: negative && !limbs.is_empty(), limbs }
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 2 {
+            return None;
+        }
+
+        // Two limbs are below 10^18, which always fits.
+        let magnitude: i64 = self.limbs.iter().rev()
+            .fold(0, |acc, &limb| acc * BASE as i64 + limb as i64);
+        Some(if self.negative { -magnitude } else { magnitude })
+    }
+
+    fn wrapping_i32(&self) -> i32 {
+        let magnitude: u32 = self.limbs.iter().rev()
+            .fold(0u32, |acc, &limb| acc.wrapping_mul(BASE as u32).wrapping_add(limb));
+        let low: u32 = if self.negative { magnitude.wrapping_neg() } else { magnitude };
+        low as i32
+    }
+
+    fn neg(mut self) -> BigInt {
+        self.negative = !self.negative && !self.limbs.is_empty();
+        self
+    }
+
+    fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::normalize(self.negative, add_magnitudes(&self.limbs, &other.limbs));
+        }
+
+        // Opposite signs: the result takes the sign of the larger magnitude.
+        match compare_magnitudes(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                BigInt::normalize(other.negative, sub_magnitudes(&other.limbs, &self.limbs))
+            },
+            _ => BigInt::normalize(self.negative, sub_magnitudes(&self.limbs, &other.limbs)),
+        }
+    }
+
+    fn mul(&self, other: &BigInt) -> BigInt {
+        let mut product: Vec<u64> = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let total: u64 = product[i + j] + a as u64 * b as u64 + carry;
+                product[i + j] = total % BASE;
+                carry = total / BASE;
+            }
+            product[i + other.limbs.len()] += carry;
+        }
+
+        let limbs: Vec<u32> = product.into_iter().map(|limb| limb as u32).collect();
+        BigInt::normalize(self.negative != other.negative, limbs)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.limbs, &other.limbs),
+            (true, true) => compare_magnitudes(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        match limbs.next() {
+            Some(most_significant) => {
+                if self.negative {
+                    write!(f, "-")?;
+                }
+                write!(f, "{}", most_significant)?;
+            },
+            None => return write!(f, "0"),
+        }
+
+        for limb in limbs {
+            write!(f, "{:09}", limb)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum: Vec<u32> = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry: u32 = 0;
+    for i in 0..a.len().max(b.len()) {
+        let total: u32 = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+        sum.push(total % BASE as u32);
+        carry = total / BASE as u32;
+    }
+    sum.push(carry);
+
+    sum
+}
+
+/// Subtracts the magnitude `b` from the magnitude `a`, which must be at least as large.
+fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference: Vec<u32> = Vec::with_capacity(a.len());
+    let mut borrow: u32 = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend: u32 = b.get(i).unwrap_or(&0) + borrow;
+        if limb >= subtrahend {
+            difference.push(limb - subtrahend);
+            borrow = 0;
+        } else {
+            difference.push(limb + BASE as u32 - subtrahend);
+            borrow = 1;
+        }
+    }
+
+    difference
+}
+
+#[cfg(test)]
+mod test {
+    use super::Value;
+
+    fn value(text: &str) -> Value {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn computes_exactly_beyond_32_bits() {
+        let factorial: Value = (1..=30).fold(Value::from(1), |acc, n| acc.mul(&Value::from(n)));
+        assert_eq!(factorial.to_string(), "265252859812191058636308480000000");
+
+        let difference: Value = value("1000000000000000000000").sub(&value("1000000000000000000001"));
+        assert_eq!(difference, -1);
+        assert_eq!(value("-99999999999").add(&value("99999999999")), 0);
+        assert!(value("-10000000000000000000") < value("-9999999999999999999"));
+    }
+
+    #[test]
+    fn narrows_to_32_bits() {
+        assert_eq!(value("4294967297").wrapping_i32(), 1);
+        assert_eq!(value("-2147483649").wrapping_i32(), 2147483647);
+        assert_eq!(value("99999999999").saturating_i32(), i32::MAX);
+        assert_eq!(value("2147483647").to_i32(), Some(i32::MAX));
+        assert!("12a".parse::<Value>().is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Runs the `core` binary the way a user would.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+
+/// Writes `source` to a fresh file for a test to run.
+fn source_file(name: &str, source: &str) -> PathBuf {
+    let path: PathBuf = std::env::temp_dir().join(format!("core-cli-{}-{}", std::process::id(),
+                                                          name));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+fn core(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_core"))
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// Runs the binary with `stdin` piped to it.
+fn pipe(args: &[&str], stdin: &str) -> Output {
+    let mut child: Child = Command::new(env!("CARGO_BIN_EXE_core"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn parse_prints_without_running() {
+    let file: PathBuf = source_file("parse.core", "program int X;begin read X;\n\
+                                                  if (X>0) then write X; end; end");
+    let output: Output = core(&["parse", &file.to_string_lossy()]);
+    fs::remove_file(&file).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout),
+               "program\n    int X;\nbegin\n    read X;\n    if (X > 0) then\n        \
+                write X;\n    end;\nend\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn exits_with_a_status_for_each_kind_of_error() {
+    let cases: [(&str, &str, i32); 5] = [
+        ("lexical.core", "program int X; begin X = 1 $; end", 3),
+        ("syntax.core", "program int X; begin X = 1 end", 4),
+        ("semantic.core", "program int X; begin X = 1; write Y; end", 5),
+        ("runtime.core", "program int X; begin read X; write X; end", 6),
+        ("fine.core", "program int X; begin X = 1; write X; end", 0),
+    ];
+
+    for &(name, source, status) in &cases {
+        let file: PathBuf = source_file(name, source);
+        let output: Output = core(&["run", &file.to_string_lossy()]);
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(output.status.code(), Some(status), "{}", name);
+    }
+
+    assert_eq!(core(&["check", "no-such-file.core"]).status.code(), Some(7));
+    assert_eq!(core(&["parse"]).status.code(), Some(2));
+    assert_eq!(core(&["--wrapping", "--bigint", "program.core"]).status.code(), Some(2));
+}
+
+#[test]
+fn traces_each_statement_to_stderr() {
+    let file: PathBuf = source_file("trace.core", "program int X; begin X = 2;\n\
+                                                  while (X > 1) loop X = X - 1; end; end");
+    let output: Output = core(&[&file.to_string_lossy(), "--trace"]);
+    fs::remove_file(&file).unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8_lossy(&output.stderr),
+               "trace: 1:22 X = 2; -> X = 2\n\
+                trace: 2:1 while (X > 1) loop -> true\n\
+                trace: 2:20 X = X - 1; -> X = 1\n\
+                trace: 2:1 while (X > 1) loop -> false\n");
+}
+
+#[test]
+fn prints_version_and_help() {
+    let version: Output = core(&["--version"]);
+    assert!(version.status.success());
+    assert_eq!(String::from_utf8_lossy(&version.stdout),
+               format!("core {}\n", env!("CARGO_PKG_VERSION")));
+
+    let help: Output = core(&["--help"]);
+    assert!(help.status.success());
+    assert!(String::from_utf8_lossy(&help.stdout).contains("Exit codes:"));
+}
+
+#[test]
+fn runs_programs_from_stdin_and_the_command_line() {
+    let inline: Output = core(&["run", "-e", "program int X; begin X = 1; write X; end"]);
+    assert!(inline.status.success());
+    assert_eq!(String::from_utf8_lossy(&inline.stdout), "1\n");
+
+    let piped: Output = pipe(&["run", "-"], "program int X; begin X = 2; write X; end");
+    assert!(piped.status.success());
+    assert_eq!(String::from_utf8_lossy(&piped.stdout), "2\n");
+
+    // Stdin holds the program, so `read` has nothing to take without `--input`.
+    let no_input: Output = pipe(&["run", "-"], "program int X; begin read X; write X; end");
+    assert_eq!(no_input.status.code(), Some(6));
+    assert!(no_input.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&no_input.stderr)
+        .contains("input ended before a value was read for X"));
+
+    let broken: Output = core(&["-e", "program int X; begin X = 1 end"]);
+    assert_eq!(broken.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&broken.stderr).contains("--> <inline>:1:28"));
+}
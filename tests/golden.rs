@@ -0,0 +1,63 @@
+//! Runs the golden-file tests under `test/`, both through the library and the `core` binary.
+
+extern crate core_interpreter;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use core_interpreter::golden;
+use core_interpreter::golden::Summary;
+
+fn milestone(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test").join(name)
+}
+
+/// Runs the tests in a milestone directory, failing with the report if any of them fail.
+fn run_milestone(name: &str) -> Summary {
+    let mut report: Vec<u8> = Vec::new();
+    let summary: Summary = golden::run_tests(&milestone(name), &mut report).unwrap();
+
+    assert_eq!(summary.failed, 0, "{}", String::from_utf8_lossy(&report));
+    summary
+}
+
+#[test]
+fn tokenizer_matches_milestone_0_1_0() {
+    assert_eq!(run_milestone("milestone-0.1.0").passed, 10);
+}
+
+#[test]
+fn tokenizer_matches_milestone_0_2_0() {
+    assert_eq!(run_milestone("milestone-0.2.0").passed, 2);
+}
+
+#[test]
+fn programs_match_milestone_0_3_0() {
+    assert_eq!(run_milestone("milestone-0.3.0").passed, 8);
+}
+
+#[test]
+fn core_test_reports_failures_with_a_diff() {
+    let passing: Output = Command::new(env!("CARGO_BIN_EXE_core"))
+        .args(["test", &milestone("milestone-0.2.0").to_string_lossy()])
+        .output()
+        .unwrap();
+    assert!(passing.status.success());
+    assert!(String::from_utf8_lossy(&passing.stdout).contains("test result: ok. 2 passed"));
+
+    let dir: PathBuf = std::env::temp_dir().join(format!("core-golden-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("double.core"), "program int X; begin read X; X = X * 2; write X; end")
+        .unwrap();
+    std::fs::write(dir.join("doubleinput"), "21").unwrap();
+    std::fs::write(dir.join("doubleexpected"), "24\n").unwrap();
+
+    let failing: Output = Command::new(env!("CARGO_BIN_EXE_core"))
+        .args(["test", &dir.to_string_lossy()])
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(failing.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&failing.stdout)
+            .contains("--- doubleexpected\n+++ actual\n@@ -1,1 +1,1 @@\n-24\n+42\n"));
+}